@@ -0,0 +1,154 @@
+use crate::lang::*;
+use crate::utils::config::SearchParams;
+use super::*;
+
+static BEAM_WIDTH: usize = 400; // partial words kept between keys
+
+/// A partial word, with the keys its letters were matched to.
+/// The words it can become are read from the dictionary node it has reached.
+#[derive(Debug, Clone)]
+struct Hypothesis {
+    node: u32,
+    matched: Vec<usize>, // index of the key each letter was matched on
+    doubles: Vec<usize>, // index of the keys a double letter was matched on
+    elision: Option<usize>, // the elision the word follows, eg. "l'" in "l'eau"
+}
+
+impl Hypothesis {
+    /// Letters on heavy keys count for the word, heavy keys it skips count against it.
+    fn score(&self, keys: &[Candidate]) -> i64 {
+        let matched: i64 = self.matched.iter().map(|index| keys[*index].weight as i64).sum();
+        let total: i64 = keys.iter().map(|key| key.weight as i64).sum();
+        matched * 2 - total
+    }
+}
+
+/// Decodes a glide as it happens, following the dictionary one key at a time.
+/// Each new key either extends a partial word with its letter, or is passed over,
+/// and only the best partial words are kept, so the work per key stays the same
+/// however large the dictionary is.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    hypotheses: Vec<Hypothesis>,
+    keys: usize, // keys decoded so far
+}
+
+impl Decoder {
+    pub fn reset(&mut self) {
+        self.hypotheses.clear();
+        self.keys = 0;
+    }
+
+    /// Extends the partial words with the keys added since the last update.
+    pub fn update(&mut self, trie: &WordTrie, keys: &[Candidate]) {
+        for index in self.keys..keys.len() {
+            // only letter keys can be part of a word
+            let letters = unicode::key_letters(&keys[index].text);
+
+            // words must start on the first key
+            if index == 0 {
+                let root = Hypothesis {
+                    node: WordTrie::ROOT,
+                    matched: Vec::new(),
+                    doubles: Vec::new(),
+                    elision: None,
+                };
+                self.hypotheses = letters.map(|letters| Self::extend(trie, &root, &letters, index)).unwrap_or_default();
+                continue
+            }
+
+            let Some(letters) = letters else {
+                continue
+            };
+            let extended: Vec<Hypothesis> = self.hypotheses
+                .iter()
+                .flat_map(|hypothesis| Self::extend(trie, hypothesis, &letters, index))
+                .collect();
+            self.hypotheses.extend(extended);
+            self.prune(&keys[..=index]);
+        }
+        self.keys = keys.len();
+    }
+
+    /// The letter on the key, and a double letter on the same key, when the dictionary has them.
+    /// The letters are folded, and can be more than one char for letters written as a cluster.
+    /// When the letters complete an elision, a word can also start again after it.
+    fn extend(trie: &WordTrie, hypothesis: &Hypothesis, letters: &str, index: usize) -> Vec<Hypothesis> {
+        let Some(node) = trie.follow(hypothesis.node, letters) else {
+            return Vec::new()
+        };
+        let mut matched = hypothesis.matched.clone();
+        matched.push(index);
+        let single = Hypothesis {
+            node,
+            matched,
+            doubles: hypothesis.doubles.clone(),
+            elision: hypothesis.elision,
+        };
+
+        let mut extended = Vec::new();
+        if hypothesis.elision.is_none() {
+            extended.extend(trie.elisions_at(node).map(|elision| Hypothesis {
+                node: WordTrie::ROOT,
+                matched: single.matched.clone(),
+                doubles: single.doubles.clone(),
+                elision: Some(elision),
+            }));
+        }
+
+        if let Some(node) = trie.follow(node, letters) {
+            let mut doubles = single.doubles.clone();
+            doubles.push(index);
+            extended.push(Hypothesis {
+                node,
+                matched: single.matched.clone(),
+                doubles,
+                elision: single.elision,
+            });
+        }
+        extended.push(single);
+        extended
+    }
+
+    /// Drops duplicate and low scoring partial words.
+    fn prune(&mut self, keys: &[Candidate]) {
+        let mut scored: Vec<(i64, Hypothesis)> = self.hypotheses
+            .drain(..)
+            .map(|hypothesis| (hypothesis.score(keys), hypothesis))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+
+        // the same letters ending on the same key can only differ in score
+        let mut seen = std::collections::HashSet::new();
+        self.hypotheses = scored
+            .into_iter()
+            .map(|(_, hypothesis)| hypothesis)
+            .filter(|hypothesis| seen.insert((hypothesis.node, hypothesis.matched.last().copied(), hypothesis.elision)))
+            .take(BEAM_WIDTH)
+            .collect();
+    }
+
+    /// Whole words that fit the glide so far, with how well they follow the key path.
+    /// Words that only differ by accents share the score, and are told apart by the language model.
+    /// Keys below min_weight were only brushed past, so letters can't be matched on them.
+    /// Double letters score extra on keys the finger paused or looped on.
+    /// When complete is true the glide has ended, so the word must also end on the last key.
+    pub fn words(&self, trie: &WordTrie, keys: &[Candidate], complete: bool, params: &SearchParams) -> Vec<(String, f32)> {
+        let min_weight = params.min_weight;
+        let total_weight: u32 = keys.iter().filter(|key| key.weight >= min_weight).map(|key| key.weight).sum();
+        let last_key = keys.iter().rposition(|key| key.weight >= min_weight);
+
+        self.hypotheses
+            .iter()
+            .filter(|hypothesis| trie.is_word(hypothesis.node))
+            .filter(|hypothesis| hypothesis.matched.iter().all(|index| keys[*index].weight >= min_weight))
+            .filter(|hypothesis| !complete || hypothesis.matched.last().copied() == last_key)
+            .flat_map(|hypothesis| {
+                let matched_weight: u32 = hypothesis.matched.iter().map(|index| keys[*index].weight).sum();
+                let doubles = hypothesis.doubles.iter().filter(|index| keys[**index].is_double(params)).count() as u32;
+                let score = matched_weight as f32 * 2.0 - total_weight as f32 + (doubles * params.double_weight) as f32;
+                trie.words_after(hypothesis.node, hypothesis.elision).map(move |word| (word, score))
+            })
+            .collect()
+    }
+}
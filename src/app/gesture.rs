@@ -16,7 +16,6 @@ use iced::{
         Duration, 
         Instant,
     }, 
-    Color, 
     Element, 
     Length, 
     Point, 
@@ -32,16 +31,18 @@ use crate::{
     utils::*,
 };
 
-static FADE_DURATION: u128 = 800; // ms
 static ACTION_GESTURE_DURATION: u128 = 250; // ms
+static WRITE_PAUSE: u64 = 500; // ms without a stroke that ends a handwritten character
 static MIN_DISTANCE: f32 = 15.0; // pixels
-static MAX_WIDTH: f32 = 25.0; // Max initial width
 static MAX_OPACITY: f32 = 0.5; // Max initial opacity
-static COLOR: Color = Color::from_rgba(0.6, 0.8, 1.0, 1.0);
 
 #[derive(Debug, Clone)]
 pub enum Message {
     UpdateHistory,
+    RecordShape(String), // the next gesture is saved as a shape with the name
+    ForgetShape(String),
+    SetWriting(bool), // gestures are handwriting strokes
+    EndCharacter(usize), // the pause after a stroke, with the number of strokes at the time
 }
 
 /// Handles the gesture ribbon.  
@@ -49,6 +50,11 @@ pub enum Message {
 pub struct GestureHandler {
     pub history: Vec<Gesture>,
     pub current_gesture: Option<Gesture>,
+    shapes: ShapeRecognizer,
+    recording_shape: Option<String>, // name the next gesture is recorded as
+    writing: bool, // gestures are handwriting strokes
+    strokes: Vec<Vec<Point>>, // strokes of the character being written
+    handwriting: HandwritingRecognizer,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -68,7 +74,7 @@ pub struct GestureData {
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ActionDirection {
     TopLeft,
     Top,
@@ -81,11 +87,63 @@ pub enum ActionDirection {
     LongPress,
 }
 
+impl ActionDirection {
+    pub const ALL: [ActionDirection; 9] = [
+        ActionDirection::TopLeft,
+        ActionDirection::Top,
+        ActionDirection::TopRight,
+        ActionDirection::Right,
+        ActionDirection::BottomRight,
+        ActionDirection::Bottom,
+        ActionDirection::BottomLeft,
+        ActionDirection::Left,
+        ActionDirection::LongPress,
+    ];
+
+    /// Stable name used in the config file.
+    pub fn id(&self) -> &'static str {
+        match self {
+            ActionDirection::TopLeft => "top_left",
+            ActionDirection::Top => "top",
+            ActionDirection::TopRight => "top_right",
+            ActionDirection::Right => "right",
+            ActionDirection::BottomRight => "bottom_right",
+            ActionDirection::Bottom => "bottom",
+            ActionDirection::BottomLeft => "bottom_left",
+            ActionDirection::Left => "left",
+            ActionDirection::LongPress => "long_press",
+        }
+    }
+}
+
+impl std::fmt::Display for ActionDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}",
+            match self {
+                ActionDirection::TopLeft => "swipe up left",
+                ActionDirection::Top => "swipe up",
+                ActionDirection::TopRight => "swipe up right",
+                ActionDirection::Right => "swipe right",
+                ActionDirection::BottomRight => "swipe down right",
+                ActionDirection::Bottom => "swipe down",
+                ActionDirection::BottomLeft => "swipe down left",
+                ActionDirection::Left => "swipe left",
+                ActionDirection::LongPress => "long press",
+            }
+        )
+    }
+}
+
 impl GestureHandler {
     pub fn new() -> Self {
         GestureHandler {
             history: Vec::new(),
             current_gesture: None,
+            shapes: ShapeRecognizer::load(),
+            recording_shape: None,
+            writing: false,
+            strokes: Vec::new(),
+            handwriting: HandwritingRecognizer::default(),
         }
     }
 
@@ -95,6 +153,53 @@ impl GestureHandler {
                 self.clear_history();
                 Task::none()
             }
+            Message::RecordShape(name) => {
+                info!("Recording shape: {}", name);
+                self.recording_shape = Some(name);
+                Task::none()
+            }
+            Message::ForgetShape(name) => {
+                info!("Forgot shape: {}", name);
+                self.shapes.remove(&name);
+                self.save_shapes();
+                self.shape_names()
+            }
+            Message::SetWriting(writing) => {
+                self.writing = writing;
+                self.strokes.clear();
+                Task::none()
+            }
+            Message::EndCharacter(count) => {
+                // another stroke has been started since, so the character isn't finished
+                if count != self.strokes.len() || self.strokes.is_empty() {
+                    return Task::none()
+                }
+                let candidates = self.handwriting.recognize(&self.strokes);
+                self.strokes.clear();
+                info!("Handwriting: {:?}", candidates);
+                Task::done(search::Message::Write(candidates)).map(main_app::Message::SearchHandler)
+            }
+        }
+    }
+
+    /// Sends the names of the recorded shapes to the views, for the settings.
+    pub fn shape_names(&self) -> Task<main_app::Message> {
+        Task::done(view::Message::Shapes(self.shapes.names())).map(main_app::Message::ViewHandler)
+    }
+
+    /// True while gestures are handwriting strokes rather than glides.
+    pub fn is_writing(&self) -> bool {
+        self.writing
+    }
+
+    /// True while waiting for a shape to be drawn to record it.
+    pub fn is_recording_shape(&self) -> bool {
+        self.recording_shape.is_some()
+    }
+
+    fn save_shapes(&self) {
+        if let Err(err) = self.shapes.save() {
+            error!("Failed to save shapes, with error: {}", err);
         }
     }
 
@@ -110,7 +215,7 @@ impl GestureHandler {
         let now = Instant::now();
         self.history.retain(|gesture| {
             if let Some(end_instant) = gesture.end_instant {
-                now.duration_since(end_instant).as_millis() < FADE_DURATION
+                now.duration_since(end_instant).as_millis() < theme::get().ribbon_fade as u128
             } else {
                 true
             }
@@ -132,9 +237,11 @@ impl GestureHandler {
         Task::none()
     }
 
-    pub fn end(&mut self) -> Task<main_app::Message> {
-        let return_task = Task::perform(async {
-            async_std::task::sleep(Duration::from_millis(FADE_DURATION as u64 + 10)).await;
+    /// Ends the gesture, shape is true if it can be a drawn shape rather than a glided word.
+    pub fn end(&mut self, shape: bool) -> Task<main_app::Message> {
+        let fade = theme::get().ribbon_fade;
+        let return_task = Task::perform(async move {
+            async_std::task::sleep(Duration::from_millis(fade + 10)).await;
             main_app::Message::GestureHandler(Message::UpdateHistory)
         }, |result| result);
 
@@ -145,6 +252,20 @@ impl GestureHandler {
             gesture.end_instant = Some(Instant::now());
             self.history.push(gesture.clone()); // clone to history
 
+            if let Some(name) = self.recording_shape.take() {
+                return Task::batch(vec![
+                    self.record_shape(name, &gesture),
+                    return_task,
+                ]);
+            }
+
+            if self.writing {
+                return Task::batch(vec![
+                    self.add_stroke(&gesture),
+                    return_task,
+                ]);
+            }
+
             match gesture.end_instant.unwrap().duration_since(gesture.start_instant.unwrap()).as_millis() {
                 duration if duration < ACTION_GESTURE_DURATION => {
                     return Task::batch(vec![
@@ -152,6 +273,12 @@ impl GestureHandler {
                         return_task,
                     ]);
                 }
+                _ if shape => {
+                    return Task::batch(vec![
+                        self.handle_shape_gesture(&gesture),
+                        return_task,
+                    ]);
+                }
                 _ => {
                     return Task::batch(vec![
                         self.handle_view_gesture(gesture),
@@ -163,6 +290,11 @@ impl GestureHandler {
         return_task
     }
 
+    /// Drops the current gesture without acting on it, eg. when a second finger lands.
+    pub fn cancel(&mut self) {
+        self.current_gesture = None;
+    }
+
     pub fn update_move(&mut self, position: Point) -> Task<main_app::Message> {
         if self.current_gesture.is_none() {
             let _ = self.start();
@@ -248,7 +380,43 @@ impl GestureHandler {
             x if x < 290.0 => ActionDirection::Left,
             _ => ActionDirection::TopLeft,
         };
-        Task::done(view::Message::ActionGesture(direction)).map(main_app::Message::ViewHandler)
+        // the search knows which key the gesture started on
+        Task::done(search::Message::ActionGesture(direction, Some(start))).map(main_app::Message::SearchHandler)
+    }
+
+    fn handle_shape_gesture(&mut self, gesture: &Gesture) -> Task<main_app::Message> {
+        let points: Vec<Point> = gesture.buffer.iter().map(|data| data.point).collect();
+        match self.shapes.recognize(&points) {
+            Some((name, score)) => {
+                info!("Shape: {} {:.2}", name, score);
+                Task::done(view::Message::ShapeGesture(name)).map(main_app::Message::ViewHandler)
+            }
+            None => {
+                info!("No shape recognised");
+                Task::none()
+            }
+        }
+    }
+
+    /// Adds a stroke to the character being written, which is read once the user pauses.
+    fn add_stroke(&mut self, gesture: &Gesture) -> Task<main_app::Message> {
+        self.strokes.push(gesture.buffer.iter().map(|data| data.point).collect());
+        let count = self.strokes.len();
+        Task::perform(async move {
+            async_std::task::sleep(Duration::from_millis(WRITE_PAUSE)).await;
+            main_app::Message::GestureHandler(Message::EndCharacter(count))
+        }, |result| result)
+    }
+
+    fn record_shape(&mut self, name: String, gesture: &Gesture) -> Task<main_app::Message> {
+        let points: Vec<Point> = gesture.buffer.iter().map(|data| data.point).collect();
+        if !self.shapes.add(&name, &points) {
+            warn!("Shape {} is too small to record", name);
+            return self.shape_names()
+        }
+        info!("Recorded shape: {}", name);
+        self.save_shapes();
+        self.shape_names()
     }
 
     fn handle_view_gesture(&mut self, _gesture: Gesture) -> Task<main_app::Message> {
@@ -274,6 +442,10 @@ impl<'a> MeshRibbon<'a> {
     pub fn draw_mesh(&self, gesture: &Gesture, renderer: &mut Renderer, viewport: &Rectangle) {
         // points are all stored in gesture.buffer, which is a Vector of GestureData {Point, Instant}
         let now = Instant::now();
+        let (fade, max_width, ribbon) = {
+            let theme = theme::get();
+            (theme.ribbon_fade as u128, theme.ribbon_width, theme.ribbon)
+        };
 
         // collect all points that are younger than fade duration
         // also do the reverse here
@@ -281,7 +453,7 @@ impl<'a> MeshRibbon<'a> {
             .iter()
             .filter(|data| {
                 let time_elapsed = now.duration_since(data.instant).as_millis();
-                time_elapsed <= FADE_DURATION
+                time_elapsed <= fade
             })
             .rev()
             .collect();
@@ -297,10 +469,10 @@ impl<'a> MeshRibbon<'a> {
             .rev()
             .flat_map(|(_i, data)| {
                 let time_elapsed = now.duration_since(data.instant).as_millis();
-                let progress = (FADE_DURATION - time_elapsed) as f32 / FADE_DURATION as f32;
-                let width = (MAX_WIDTH * progress).max(1.0); // Ensure width doesn't go below 1.0
+                let progress = (fade - time_elapsed) as f32 / fade.max(1) as f32;
+                let width = (max_width * progress).max(1.0); // Ensure width doesn't go below 1.0
                 let opacity = (MAX_OPACITY * progress).max(0.0);   // Ensure opacity doesn't go below 0.0
-                let color = color::pack(COLOR.scale_alpha(opacity));
+                let color = color::pack(ribbon.scale_alpha(opacity));
                 let half_normal = functions::multiply_point(data.normal, width * 0.5);
                 let left = functions::add_point(data.point, half_normal);
                 let right = functions::add_point(data.point, functions::invert_point(half_normal));
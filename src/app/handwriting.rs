@@ -0,0 +1,183 @@
+use iced::Point;
+use crate::utils::*;
+
+static SAMPLE_POINTS: usize = 32; // points each character is resampled to
+static MATCH_EPSILON: f32 = 0.5; // 0-1, lower tries more starting points when matching clouds
+static TEMPERATURE: f32 = 1.0; // how quickly likelihood falls as characters fit worse
+static MAX_CANDIDATES: usize = 5;
+
+/// A character as a cloud of points, normalised so it can be compared to any other.
+#[derive(Debug, Clone, PartialEq)]
+struct Template {
+    character: String,
+    points: Vec<Point>,
+}
+
+/// Recognises handwritten characters with the $P point cloud recogniser.
+/// The strokes of a character are resampled to evenly spaced points, scaled and centered,
+/// and matched point to point against each template, so the order and direction of the strokes don't matter.
+/// Templates for Latin letters and digits are bundled, see `res/handwriting`.
+#[derive(Debug, Clone)]
+pub struct HandwritingRecognizer {
+    templates: Vec<Template>,
+}
+
+impl Default for HandwritingRecognizer {
+    fn default() -> Self {
+        HandwritingRecognizer::parse(globals::HANDWRITING_LATIN)
+    }
+}
+
+impl HandwritingRecognizer {
+    /// Reads templates, one character per line: the character, a tab,
+    /// then its strokes separated by " | ", each a list of `x,y` points.
+    pub fn parse(text: &str) -> Self {
+        let mut templates = Vec::new();
+        for line in text.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue
+            }
+            let Some((character, strokes)) = line.split_once('\t') else {
+                warn!("Skipping malformed handwriting line: {:?}", line);
+                continue
+            };
+            let strokes: Option<Vec<Vec<Point>>> = strokes
+                .split('|')
+                .map(|stroke| stroke
+                    .split_whitespace()
+                    .map(|point| {
+                        let (x, y) = point.split_once(',')?;
+                        Some(Point::new(x.parse().ok()?, y.parse().ok()?))
+                    })
+                    .collect())
+                .collect();
+            match strokes.and_then(|strokes| normalize(&strokes)) {
+                Some(points) => templates.push(Template {
+                    character: character.to_string(),
+                    points,
+                }),
+                None => warn!("Skipping malformed handwriting line: {:?}", line),
+            }
+        }
+        HandwritingRecognizer { templates }
+    }
+
+    /// The characters the strokes most likely are, with a likelihood for each, 0-1, best first.
+    pub fn recognize(&self, strokes: &[Vec<Point>]) -> Vec<(String, f32)> {
+        let Some(points) = normalize(strokes) else {
+            return Vec::new()
+        };
+        let mut candidates: Vec<(String, f32)> = self.templates
+            .iter()
+            .map(|template| (template.character.clone(), greedy_cloud_match(&points, &template.points)))
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        // keep the best fit of each character
+        let mut seen: Vec<String> = Vec::new();
+        candidates.retain(|(character, _)| match seen.contains(character) {
+            true => false,
+            false => {
+                seen.push(character.clone());
+                true
+            }
+        });
+        candidates.truncate(MAX_CANDIDATES);
+
+        // relative to the best fit, so the likelihoods sum to 1
+        let best = candidates.first().map_or(0.0, |(_, distance)| *distance);
+        let weights: Vec<f32> = candidates.iter().map(|(_, distance)| (-(distance - best) / TEMPERATURE).exp()).collect();
+        let total: f32 = weights.iter().sum();
+        candidates
+            .into_iter()
+            .zip(weights)
+            .map(|((character, _), weight)| (character, weight / total))
+            .collect()
+    }
+}
+
+
+/// Resamples, scales and centers the strokes as one cloud, None if there is nothing to read.
+fn normalize(strokes: &[Vec<Point>]) -> Option<Vec<Point>> {
+    let points = resample(strokes, SAMPLE_POINTS)?;
+
+    // scaled the same on both axes, so a tall "l" doesn't become an "o"
+    let (min_x, max_x) = points.iter().fold((f32::MAX, f32::MIN), |(min, max), point| (min.min(point.x), max.max(point.x)));
+    let (min_y, max_y) = points.iter().fold((f32::MAX, f32::MIN), |(min, max), point| (min.min(point.y), max.max(point.y)));
+    let size = (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+    let count = points.len() as f32;
+    let center = Point::new(
+        points.iter().map(|point| point.x).sum::<f32>() / count,
+        points.iter().map(|point| point.y).sum::<f32>() / count,
+    );
+    Some(points.iter().map(|point| Point::new((point.x - center.x) / size, (point.y - center.y) / size)).collect())
+}
+
+/// Evenly spaced points along the strokes, without joining one stroke to the next.
+fn resample(strokes: &[Vec<Point>], count: usize) -> Option<Vec<Point>> {
+    let length: f32 = strokes.iter().flat_map(|stroke| stroke.windows(2)).map(|pair| pair[0].distance(pair[1])).sum();
+    let first = *strokes.iter().find_map(|stroke| stroke.first())?;
+    if length <= 0.0 {
+        return Some(vec![first; count]) // a dot
+    }
+
+    let interval = length / (count - 1) as f32;
+    let mut resampled = vec![first];
+    let mut covered = 0.0;
+    for stroke in strokes.iter().filter(|stroke| !stroke.is_empty()) {
+        let mut previous = stroke[0];
+        let mut index = 1;
+        while index < stroke.len() {
+            let point = stroke[index];
+            let distance = previous.distance(point);
+            if covered + distance >= interval && distance > 0.0 {
+                let t = (interval - covered) / distance;
+                let new_point = Point::new(previous.x + t * (point.x - previous.x), previous.y + t * (point.y - previous.y));
+                resampled.push(new_point);
+                previous = new_point;
+                covered = 0.0;
+            } else {
+                covered += distance;
+                previous = point;
+                index += 1;
+            }
+        }
+    }
+    // rounding can leave the last point off
+    let last = *strokes.iter().rev().find_map(|stroke| stroke.last())?;
+    while resampled.len() < count {
+        resampled.push(last);
+    }
+    resampled.truncate(count);
+    Some(resampled)
+}
+
+/// Distance between the clouds, trying a few starting points in both directions, lower is a better fit.
+fn greedy_cloud_match(points: &[Point], template: &[Point]) -> f32 {
+    let count = points.len().min(template.len());
+    let step = ((count as f32).powf(1.0 - MATCH_EPSILON) as usize).max(1);
+    (0..count)
+        .step_by(step)
+        .map(|start| cloud_distance(points, template, start).min(cloud_distance(template, points, start)))
+        .fold(f32::MAX, f32::min)
+}
+
+/// Matches each point to the closest point of the other cloud that isn't matched yet,
+/// weighting the earlier matches more, as they had more points to choose from.
+fn cloud_distance(a: &[Point], b: &[Point], start: usize) -> f32 {
+    let count = a.len().min(b.len());
+    let mut matched = vec![false; count];
+    let mut sum = 0.0;
+    for offset in 0..count {
+        let point = a[(start + offset) % count];
+        let Some((index, distance)) = (0..count)
+            .filter(|index| !matched[*index])
+            .map(|index| (index, point.distance(b[index])))
+            .min_by(|x, y| x.1.total_cmp(&y.1)) else {
+            break
+        };
+        matched[index] = true;
+        sum += (1.0 - offset as f32 / count as f32) * distance;
+    }
+    sum
+}
@@ -6,7 +6,6 @@ use iced::{
     mouse, 
     time::{
         self, 
-        Instant, 
         Duration
     }, 
     touch, 
@@ -18,8 +17,8 @@ use iced::{
 use iced_runtime::Action;
 use super::*;
 
-static LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
-static MOVE_THRESHOLD: f32 = 10.0;
+pub static LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+pub static MOVE_THRESHOLD: f32 = 10.0; // pixels a pointer moves before a tap is a glide, keys use it too so they agree
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -28,39 +27,54 @@ pub enum Message {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PressType {
-    None,
     Tap,
     LongPress,
     Gesture,
 }
 
+/// Identifies a pointer, so each finger is followed on its own.
+/// Pens arrive from the compositor as the mouse or as a touch, and are followed the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerId {
+    Mouse, // the left button
+    Finger(u64),
+}
+
+/// A pointer that is down on the keyboard, and what it is doing so far.
+#[derive(Debug, Clone)]
+pub struct Pointer {
+    pub id: PointerId,
+    pub start: Point, // where it was pressed
+    pub position: Point, // where it is now
+    pub press: PressType,
+}
+
 /// Handles the user inputs.  
 #[derive(Clone, Debug)]
 pub struct InputHandler {
-    left_mouse: PressType,
+    pointer: Option<Pointer>, // the pointer that taps and glides, only one at a time
     rmouse_down: bool,
-    finger_presses: Vec<(u64, Point, Instant)>, // id, pos, time
+    multi_touch: MultiTouch, // fingers on the screen, for gestures with more than one
     long_press_timer_enabled: bool,
-    start_cursor_position: Option<Point>, // start position of press
-    last_cursor_position: Option<Point>, // last cursor position, so we know position of click
     cursor_position: Option<Point>, // cursor position, so we know position of click, bug in iced not giving the point on click!
 }
 
 /// Notes on how this works:
 /// - The InputHandler is responsible for handling all user inputs.
-/// - When the user taps the screen, the InputHandler will start a timer, and store a start position.
-/// - If the user lifts their finger before the timer ends, without moving, the InputHandler will consider it a tap.
-/// - If the user moves their finger before the timer ends, the InputHandler will consider it a gesture.
-/// - If the user presses their finger for a long time, without moving, the InputHandler will consider it a long press.
+/// - The mouse and each finger are pointers, they all go through `press`, `moved` and `release`,
+///   so taps, long presses and glides work the same whichever is used.
+/// - When a pointer is pressed, the InputHandler will start a timer, and store a start position.
+/// - If the pointer is lifted before the timer ends, without moving, the InputHandler will consider it a tap.
+/// - If the pointer moves before the timer ends, the InputHandler will consider it a gesture.
+/// - If the pointer is held for a long time, without moving, the InputHandler will consider it a long press.
+/// - A second finger turns the press into a multi-finger gesture, see `MultiTouch`.
 impl InputHandler {
     pub fn new() -> Self {
         InputHandler {
-            left_mouse: PressType::None,
+            pointer: None,
             rmouse_down: false,
-            finger_presses: Vec::new(),
+            multi_touch: MultiTouch::default(),
             long_press_timer_enabled: false,
-            start_cursor_position: None,
-            last_cursor_position: None,
             cursor_position: None,
         }
     }
@@ -73,8 +87,11 @@ impl InputHandler {
                     return Task::none()
                 }
                 self.long_press_timer_enabled = false;
-                self.left_mouse = PressType::LongPress;
-                return Task::done(view::Message::ActionGesture(ActionDirection::LongPress)).map(main_app::Message::ViewHandler);
+                let Some(pointer) = self.pointer.as_mut().filter(|pointer| pointer.press == PressType::Tap) else {
+                    return Task::none()
+                };
+                pointer.press = PressType::LongPress;
+                return Task::done(search::Message::ActionGesture(ActionDirection::LongPress, Some(pointer.start))).map(main_app::Message::SearchHandler);
             },
         }
     }
@@ -83,9 +100,19 @@ impl InputHandler {
         match event {
             Event::Window(event) => {
                 match event {
-                    iced::window::Event::Opened{..} => {
+                    iced::window::Event::Opened{ size, .. } => {
+                        search_handler.set_window_size(*size);
                         search_handler.load_dictionary();
-                        return Task::none()
+                        search_handler.predict();
+                        return Task::batch([search_handler.learned_words(), search_handler.languages(), gesture_handler.shape_names()])
+                    }
+                    iced::window::Event::Resized(size) => {
+                        search_handler.set_window_size(*size);
+                        Task::none()
+                    }
+                    iced::window::Event::CloseRequested | iced::window::Event::Closed => {
+                        search_handler.save();
+                        Task::none()
                     }
                     _ => Task::none()
                 }
@@ -93,8 +120,11 @@ impl InputHandler {
 
             // keyboard
             Event::Keyboard(keyboard::Event::KeyPressed {key, ..}) => match key {
-                iced::keyboard::Key::Named(Named::Escape) => iced_runtime::task::effect(Action::Exit),
-                iced::keyboard::Key::Named(Named::Backspace) => Task::none(),
+                iced::keyboard::Key::Named(Named::Escape) => {
+                    search_handler.save();
+                    iced_runtime::task::effect(Action::Exit)
+                }
+                iced::keyboard::Key::Named(Named::Backspace) => search_handler.backspace(),
                 _ => Task::none()
             }
 
@@ -104,11 +134,9 @@ impl InputHandler {
                     // button pressed
                     mouse::Event::ButtonPressed(button) => {
                         match button {
-                            mouse::Button::Left => {
-                                self.left_mouse = PressType::Tap;                
-                                self.long_press_timer_enabled = true;
-                                self.start_cursor_position = self.cursor_position;
-                                return Task::none()
+                            mouse::Button::Left => match self.cursor_position {
+                                Some(position) => self.press(PointerId::Mouse, position),
+                                None => Task::none(),
                             }
 
                             mouse::Button::Right => {
@@ -124,73 +152,17 @@ impl InputHandler {
                         // store the new cursor position
                         self.cursor_position = Some(*position);
 
-                        // left mouse movement
-                        match self.left_mouse {
-                            PressType::Tap => {
-                                // check if the cursor has moved above the threshold
-                                // do nothing if the cursor has not moved above the threshold
-                                if !self.is_above_move_threshold() {
-                                    return Task::none()
-                                }
-
-                                // reset timer
-                                self.long_press_timer_enabled = false;
-                                self.left_mouse = PressType::Gesture;
-
-                                
-                                // add start positions
-                                let _ = search_handler.start();
-                                let _ = gesture_handler.start();
-
-                                if self.last_cursor_position.is_some() {
-                                    let _ = gesture_handler.update_move(self.last_cursor_position.unwrap());
-                                    let _ = search_handler.update_move(self.last_cursor_position.unwrap());
-                                }
-
-                                // add current position
-                                return Task::batch(vec![
-                                    gesture_handler.update_move(*position), 
-                                    search_handler.update_move(*position),
-                                ])
-                            }
-                            PressType::Gesture => {
-                                return Task::batch(vec![
-                                    gesture_handler.update_move(*position), 
-                                    search_handler.update_move(*position),
-                                ])
-                            }
-                            _ => {}
-                            
-                        }
-
                         // right mouse movement
                         if self.rmouse_down {
                             return window_handler.update_move(*position);
                         }
-
-                        Task::none()
+                        self.moved(PointerId::Mouse, *position, gesture_handler, search_handler)
                     }
 
                     // button released
                     mouse::Event::ButtonReleased(button) => {
                         match button {
-                            mouse::Button::Left => {
-                                let mut result = Task::none();
-                                match self.left_mouse {
-                                    PressType::Gesture => { 
-                                        result = Task::batch(vec![
-                                            gesture_handler.end(), 
-                                            search_handler.end(),
-                                        ]);
-                                    }
-                                    _ => {}
-                                }
-                                // reset the state
-                                self.left_mouse = PressType::None;
-                                self.long_press_timer_enabled = false;
-                                self.last_cursor_position = None;
-                                return result;
-                            }
+                            mouse::Button::Left => self.release(PointerId::Mouse, gesture_handler, search_handler),
 
                             mouse::Button::Right => {
                                 self.rmouse_down = false;
@@ -209,76 +181,170 @@ impl InputHandler {
             Event::Touch(event) => {
                 match event {
                     touch::Event::FingerPressed { id, position} => {
-                        self.finger_presses.push((id.0, *position, Instant::now()));
-                        self.long_press_timer_enabled = true;
-
-                        // todo: if gesture has started and multiple fingers pressed, cancel the gesture.
-                        // todo: then if multiple fingers pressed we want to move the window instead
-                           
-                        // Check for multiple finger presses
-                        if self.finger_presses.len() >= 2 {
-                            // Get the timestamps of the two most recent finger presses
-                            let (t1, t2) = {
-                                let mut timestamps = self.finger_presses.iter().map(|(_, _, t)| t).collect::<Vec<_>>();
-                                timestamps.sort();
-                                (timestamps[0], timestamps[1])
-                            };
-
-                            // Check if the delay between the two finger presses is within a certain threshold
-                            if t2.duration_since(*t1).as_millis() < 200 { // 200ms threshold
-                                // Handle the multiple finger press event
-                                info!("Multiple finger press detected!");
-                            }
+                        let ended = self.multi_touch.press(id.0, *position);
+                        if self.multi_touch.is_multi() {
+                            // a second finger turns the glide into a multi-finger gesture, so it isn't typed
+                            self.cancel(gesture_handler, search_handler);
+                            return self.handle_touch_gesture(ended, window_handler)
                         }
-
-                        return gesture_handler.start();
+                        self.press(PointerId::Finger(id.0), *position)
                     }
                     touch::Event::FingerMoved { id, position} => {
-                        self.long_press_timer_enabled = false;
-                        
-                        if let Some((_, _, _)) = self.finger_presses.iter_mut().find(|(fid, _, _)| *fid == id.0) {
-                            if id.0 == 1 {
-                                info!("Finger 1 moved to: {position}");
-                            }
+                        let touch_gesture = self.multi_touch.move_to(id.0, *position);
+                        if self.multi_touch.is_multi() {
+                            return self.handle_touch_gesture(touch_gesture, window_handler)
+                        }
+                        self.moved(PointerId::Finger(id.0), *position, gesture_handler, search_handler)
+                    }
+                    touch::Event::FingerLifted { id, ..} => {
+                        let was_multi = self.multi_touch.is_multi();
+                        let touch_gesture = self.multi_touch.lift(id.0);
+                        if was_multi {
+                            return self.handle_touch_gesture(touch_gesture, window_handler)
                         }
-                        return gesture_handler.update_move(*position);
+                        self.release(PointerId::Finger(id.0), gesture_handler, search_handler)
                     }
-                    touch::Event::FingerLifted { id, ..} | touch::Event::FingerLost { id, ..} => {
-                        self.finger_presses.retain(|(fid, _, _)| *fid != id.0);
-                        return gesture_handler.end();
-                        // todo check for long press single finger
-                        // todo check fo release of second finger - right click
+                    touch::Event::FingerLost { id, ..} => {
+                        // the compositor took the touch away, so whatever it was doing is dropped
+                        let was_multi = self.multi_touch.is_multi();
+                        let touch_gesture = self.multi_touch.lift(id.0);
+                        if was_multi {
+                            return self.handle_touch_gesture(touch_gesture, window_handler)
+                        }
+                        if self.pointer.as_ref().is_some_and(|pointer| pointer.id == PointerId::Finger(id.0)) {
+                            self.cancel(gesture_handler, search_handler);
+                        }
+                        Task::none()
                     }
-                    //_ => {}
                 }
             },
             _ => Task::none(),
         }
     }
 
-    pub fn subscription(&self) -> Subscription<Message> {
-        match self.long_press_timer_enabled {
-            true => time::every(LONG_PRESS_DURATION).map(|_| Message::LongPressTick),
-            false => Subscription::none()
+    /// A pointer is pressed, it is a tap until it moves or is held.
+    /// Only one pointer types at a time, so a touch the compositor also reports as the mouse isn't typed twice.
+    fn press(&mut self, id: PointerId, position: Point) -> Task<main_app::Message> {
+        if self.pointer.is_some() {
+            return Task::none()
         }
+        self.pointer = Some(Pointer {
+            id,
+            start: position,
+            position,
+            press: PressType::Tap,
+        });
+        self.long_press_timer_enabled = true;
+        Task::none()
     }
 
-    /// Returns true if the distance between the last cursor position and the current cursor position is larger than
-    /// MOVE_THRESHOLD. Updates the last cursor position to the current position. If the last cursor position is None,
-    /// it sets it to the current cursor position and returns false.
-    fn is_above_move_threshold(&mut self) -> bool {
-        if self.last_cursor_position.is_none() {
-            self.last_cursor_position = self.cursor_position;
-            return false
+    /// A pointer moved, once it has moved far enough from where it was pressed it is a glide.
+    fn moved(&mut self, id: PointerId, position: Point, gesture_handler: &mut GestureHandler, search_handler: &mut SearchHandler) -> Task<main_app::Message> {
+        let Some(pointer) = self.pointer.as_mut().filter(|pointer| pointer.id == id) else {
+            return Task::none()
+        };
+        pointer.position = position;
+        match pointer.press {
+            PressType::Tap => {
+                // do nothing if the pointer has not moved above the threshold
+                if pointer.start.distance(position) <= MOVE_THRESHOLD {
+                    return Task::none()
+                }
+
+                // reset timer
+                self.long_press_timer_enabled = false;
+                pointer.press = PressType::Gesture;
+
+                // add start positions
+                let _ = search_handler.start();
+                let _ = gesture_handler.start();
+                let _ = gesture_handler.update_move(pointer.start);
+                let _ = search_handler.update_move(pointer.start);
+
+                // add current position
+                Task::batch(vec![
+                    gesture_handler.update_move(position), 
+                    search_handler.update_move(position),
+                ])
+            }
+            PressType::Gesture => Task::batch(vec![
+                gesture_handler.update_move(position), 
+                search_handler.update_move(position),
+            ]),
+            _ => Task::none()
+        }
+    }
+
+    /// A pointer is lifted, a tap types the key under it and a glide is read as a word, shape or action.
+    fn release(&mut self, id: PointerId, gesture_handler: &mut GestureHandler, search_handler: &mut SearchHandler) -> Task<main_app::Message> {
+        if !self.pointer.as_ref().is_some_and(|pointer| pointer.id == id) {
+            return Task::none()
+        }
+        // reset the state
+        let pointer = self.pointer.take().unwrap();
+        self.long_press_timer_enabled = false;
+
+        match pointer.press {
+            PressType::Gesture => { 
+                // handwriting, gestures started above the keys, and shapes being recorded, are not words
+                let shape = gesture_handler.is_writing() || gesture_handler.is_recording_shape() || search_handler.is_above_keys(pointer.start);
+                match shape {
+                    true => {
+                        search_handler.cancel();
+                        gesture_handler.end(true)
+                    }
+                    false => Task::batch(vec![
+                        gesture_handler.end(false), 
+                        search_handler.end(),
+                    ]),
+                }
+            }
+            PressType::Tap => search_handler.tap(pointer.position),
+            _ => Task::none()
         }
-        let last_cursor_position = self.last_cursor_position.unwrap();
-        let distance = self.start_cursor_position.unwrap().distance(last_cursor_position);
-        let result = distance > MOVE_THRESHOLD;
-        // only update until this changes to true
-        if result {
-            return result
+    }
+
+    /// Drops the pointer without typing anything, eg. when a second finger lands.
+    fn cancel(&mut self, gesture_handler: &mut GestureHandler, search_handler: &mut SearchHandler) {
+        if let Some(pointer) = self.pointer.take() {
+            if pointer.press == PressType::Gesture {
+                gesture_handler.cancel();
+                search_handler.cancel();
+            }
+        }
+        self.long_press_timer_enabled = false;
+    }
+
+    /// Two fingers move the window or pinch its height, three fingers swipe between views.
+    fn handle_touch_gesture(&mut self, touch_gesture: Option<TouchGesture>, window_handler: &mut WindowHandler) -> Task<main_app::Message> {
+        match touch_gesture {
+            Some(TouchGesture::Move { start, centroid }) => {
+                let start_task = match start {
+                    true => window_handler.start_move(),
+                    false => Task::none(),
+                };
+                Task::batch(vec![
+                    start_task,
+                    window_handler.update_move(centroid),
+                ])
+            }
+            Some(TouchGesture::Pinch { start, scale }) => {
+                if start {
+                    let _ = window_handler.start_resize();
+                }
+                window_handler.update_resize(scale)
+            }
+            Some(TouchGesture::EndMove) => window_handler.end_move(),
+            Some(TouchGesture::EndPinch) => window_handler.end_resize(),
+            Some(TouchGesture::Swipe(step)) => Task::done(view::Message::StepView(step)).map(main_app::Message::ViewHandler),
+            None => Task::none(),
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        match self.long_press_timer_enabled {
+            true => time::every(LONG_PRESS_DURATION).map(|_| Message::LongPressTick),
+            false => Subscription::none()
         }
-        self.last_cursor_position = self.cursor_position;
-        return result
     }
 }
\ No newline at end of file
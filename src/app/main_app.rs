@@ -11,8 +11,10 @@ use iced_layershell::{
 use iced::{
     event, 
     theme::Style, 
-    widget::stack, 
-    Color, 
+    widget::{
+        column,
+        stack,
+    }, 
     Element, 
     Event, 
     Subscription, 
@@ -30,6 +32,7 @@ pub enum Message {
     WindowHandler(window::Message),
     InputHandler(input::Message),
     ViewHandler(view::Message),
+    OutputHandler(output::Message),
     IcedEvent(Event),
     Debug(String),
 }
@@ -44,6 +47,7 @@ pub struct MainApp {
     pub window_handler: WindowHandler,
     pub input_handler: InputHandler,
     pub view_handler: ViewHandler,
+    pub output_handler: OutputHandler,
 }
 
 
@@ -55,6 +59,7 @@ impl Default for MainApp {
             window_handler: WindowHandler::new(),
             input_handler: InputHandler::new(),
             view_handler: ViewHandler::new(),
+            output_handler: OutputHandler::new(),
         }
     }
 }
@@ -77,7 +82,11 @@ impl MainApp {
 
     pub fn view(&self) -> Element<Message> {
         // TODO: should this be changed to use map??
-        stack![self.view_handler.view(), self.gesture_handler.view()].into()
+        let view = match self.view_handler.current_view().has_gesture() {
+            true => column![self.search_handler.view(), self.view_handler.view()].into(),
+            false => self.view_handler.view(),
+        };
+        stack![view, self.gesture_handler.view(), self.search_handler.preview_view()].into()
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
@@ -89,6 +98,7 @@ impl MainApp {
             Message::GestureHandler(msg) => self.gesture_handler.update(msg),
             Message::ViewHandler(msg) => self.view_handler.update(msg),
             Message::SearchHandler(msg) => self.search_handler.update(msg),
+            Message::OutputHandler(msg) => self.output_handler.update(msg),
             Message::Debug(s) => {
                 info!("{s}");
                 Task::none()
@@ -96,10 +106,11 @@ impl MainApp {
         }
     }
 
-    pub fn style(&self, theme: &iced::Theme) -> Style {
+    pub fn style(&self, _theme: &iced::Theme) -> Style {
+        let theme = theme::get();
         Style {
-            background_color: Color::from_rgba(0.21, 0.23, 0.25, 0.95),
-            text_color: theme.palette().text,
+            background_color: theme.background,
+            text_color: theme.text,
         }
     }
 
@@ -111,9 +122,11 @@ impl MainApp {
     pub fn subscription(&self) -> Subscription<Message> {
         let main_subscription = event::listen().map(Message::IcedEvent);
         let input_subscription = self.input_handler.subscription().map(Message::InputHandler);
+        let search_subscription = self.search_handler.subscription().map(Message::SearchHandler);
         Subscription::batch(vec![
             main_subscription,
             input_subscription,
+            search_subscription,
         ])
     }
 }
@@ -136,10 +149,11 @@ impl MainApp {
         }
     }
 
-    pub fn style_layershell(&self, theme: &iced::Theme) -> iced_layershell::Appearance {
+    pub fn style_layershell(&self, _theme: &iced::Theme) -> iced_layershell::Appearance {
+        let theme = theme::get();
         iced_layershell::Appearance {
-            background_color: Color::from_rgba(0.21, 0.23, 0.25, 0.95),
-            text_color: theme.palette().text,
+            background_color: theme.background,
+            text_color: theme.text,
         }
     }
 }
\ No newline at end of file
@@ -4,6 +4,14 @@ pub mod window;
 pub mod input;
 pub mod gesture;
 pub mod search;
+pub mod output;
+pub mod recorder;
+pub mod decoder;
+pub mod tap;
+pub mod targets;
+pub mod shapes;
+pub mod handwriting;
+pub mod touch;
 
 // re-export
 pub use super::app::view::*;
@@ -11,4 +19,12 @@ pub use super::app::main_app::*;
 pub use super::app::window::*;
 pub use super::app::input::*;
 pub use super::app::gesture::*;
-pub use super::app::search::*;
\ No newline at end of file
+pub use super::app::search::*;
+pub use super::app::output::*;
+pub use super::app::recorder::*;
+pub use super::app::decoder::*;
+pub use super::app::tap::*;
+pub use super::app::targets::*;
+pub use super::app::shapes::*;
+pub use super::app::handwriting::*;
+pub use super::app::touch::*;
\ No newline at end of file
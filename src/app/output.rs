@@ -0,0 +1,358 @@
+use iced::Task;
+use std::sync::mpsc::{
+    self,
+    Sender
+};
+use crate::comp::Latch;
+use crate::utils::*;
+use crate::utils::config::{
+    TypingRule,
+    TypingRules
+};
+use super::*;
+
+static MAX_HISTORY: usize = 100;
+static MAX_TEXT: usize = 200; // characters of typed text kept, to apply the typing rules
+static SMART_PUNCTUATION: [char; 6] = ['.', ',', '!', '?', ':', ';'];
+static SENTENCE_END: [char; 3] = ['.', '!', '?'];
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Commit(String), // a whole word, spaced from the previous word
+    Type(String), // text typed as is, eg. a tapped letter
+    Replace(usize, String), // deletes the characters before the cursor, then types the text
+    Backspace, // deletes the last committed word if nothing was typed after it, otherwise a character
+    DeleteWords(usize), // deletes whole words before the cursor
+    Keys(String), // presses a key chord, eg. "ctrl+c"
+    Shift, // taps shift, see `Latch::next`
+    ToggleRule(TypingRule),
+}
+
+/// Handles sending text to the focused application.
+/// Text is typed with `wtype`, which uses the wayland virtual keyboard protocol.
+/// The typing rules for spacing, punctuation and capitals are applied here,
+/// as this is where the text that has been typed is known.
+#[derive(Clone, Debug)]
+pub struct OutputHandler {
+    history: Vec<String>, // committed words, most recent last
+    text: String, // the end of the text typed so far, empty at the start of the text
+    last_commit: Option<(String, usize)>, // the last committed word and the chars typed for it, until more is typed
+    shift: Latch, // capitalises the next letter or word when latched, everything when locked
+    wtype: Sender<Vec<String>>, // arguments for each wtype run, see `spawn_wtype`
+}
+
+impl OutputHandler {
+    pub fn new() -> Self {
+        OutputHandler {
+            history: Vec::new(),
+            text: String::new(),
+            last_commit: None,
+            shift: Latch::Off,
+            wtype: spawn_wtype(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<main_app::Message> {
+        // the shift key shows whether it is latched
+        let shift = self.shift;
+        let task = self.handle(message);
+        match self.shift == shift {
+            true => task,
+            false => Task::batch(vec![
+                task,
+                Task::done(view::Message::Shift(self.shift)).map(main_app::Message::ViewHandler),
+            ]),
+        }
+    }
+
+    fn handle(&mut self, message: Message) -> Task<main_app::Message> {
+        match message {
+            Message::Commit(word) => {
+                let rules = config::get().typing.clone();
+
+                // words are separated by a space, but not from the start of the text or after a space
+                let space = match rules.auto_space && self.text.chars().last().is_some_and(|c| !c.is_whitespace()) {
+                    true => " ",
+                    false => "",
+                };
+                let word_text = match rules.auto_capitalize && is_sentence_start(&format!("{}{space}", self.text)) {
+                    true => capitalize(&word),
+                    false => word.clone(),
+                };
+                let word_text = self.shifted(&word_text);
+                let typed = format!("{space}{word_text}");
+                self.send(0, &typed);
+                self.last_commit = Some((word.clone(), typed.chars().count()));
+                self.history.push(word);
+                if self.history.len() > MAX_HISTORY {
+                    self.history.remove(0);
+                }
+                Task::none()
+            }
+            Message::Type(text) => {
+                let text = self.shifted(&text);
+                self.type_text(0, &text);
+                Task::none()
+            }
+            Message::Replace(delete, text) => {
+                self.type_text(delete, &text);
+                Task::none()
+            }
+            Message::Backspace => {
+                let Some((word, typed)) = self.last_commit.take() else {
+                    self.send(1, "");
+                    return Task::none()
+                };
+
+                // the whole word goes, with the space before it, and the search offers the other words
+                debug!("Delete committed word, {} chars", typed);
+                self.send(typed, "");
+                self.history.pop();
+                Task::done(search::Message::Reopen(word)).map(main_app::Message::SearchHandler)
+            }
+            Message::DeleteWords(count) => {
+                self.last_commit = None;
+                let known = self.text.split_whitespace().count().min(count);
+                let remaining = words_before(&self.text, known).chars().count();
+                self.send(self.text.chars().count() - remaining, "");
+
+                // the text isn't always known, eg. at the start or after a key chord, so the app deletes the rest
+                if known < count {
+                    self.text.clear();
+                    for _ in known..count {
+                        self.send_keys("ctrl+BackSpace");
+                    }
+                }
+                Task::none()
+            }
+            Message::Keys(chord) => {
+                // what the chord does to the text isn't known
+                self.last_commit = None;
+                self.text.clear();
+                self.send_keys(&chord);
+                Task::none()
+            }
+            Message::Shift => {
+                self.shift = self.shift.next();
+                Task::none()
+            }
+            Message::ToggleRule(rule) => {
+                config::update(|config| config.typing.toggle(rule));
+                Task::none()
+            }
+        }
+    }
+
+    /// Deletes then types the text, applying the typing rules.
+    /// Text replacing a word keeps the capitals the word was typed with, eg. with shift.
+    fn type_text(&mut self, delete: usize, text: &str) {
+        self.last_commit = None;
+        let rules = config::get().typing.clone();
+        let before = text_before(&self.text, delete);
+        let text = match_case(&self.text[before.len()..], text);
+        let (delete, text) = apply_rules(&rules, before, delete, &text);
+        self.send(delete, &text);
+    }
+
+    /// Applies shift to text about to be typed, a latched shift lets go once it has capitalised a letter.
+    fn shifted(&mut self, text: &str) -> String {
+        match self.shift {
+            Latch::Off => text.to_string(),
+            Latch::Latched if text.chars().any(char::is_alphabetic) => {
+                self.shift = Latch::Off;
+                capitalize(text)
+            }
+            Latch::Latched => text.to_string(),
+            Latch::Locked => text.to_uppercase(),
+        }
+    }
+
+    /// Presses backspace delete times then types the text.
+    /// The text is tracked straight away, wtype catches up in the order things were sent.
+    fn send(&mut self, delete: usize, text: &str) {
+        // what is typed can be a password, so only its length is logged
+        debug!("output: delete {}, type {} chars", delete, text.chars().count());
+        let mut args = Vec::new();
+        for _ in 0..delete {
+            args.extend(["-k".to_string(), "BackSpace".to_string()]);
+        }
+        if !text.is_empty() {
+            args.extend(["--".to_string(), text.to_string()]);
+        }
+        self.run_wtype(args);
+
+        self.text = format!("{}{text}", text_before(&self.text, delete));
+        let length = self.text.chars().count();
+        if length > MAX_TEXT {
+            self.text = self.text.chars().skip(length - MAX_TEXT).collect();
+        }
+    }
+
+    /// Presses the modifiers, taps the key, then releases the modifiers.
+    /// Names are as `wtype` takes them, eg. "ctrl", "shift", "alt", "logo", and keysyms such as "Return".
+    fn send_keys(&self, chord: &str) {
+        info!("output keys: {}", chord);
+        let mut names: Vec<&str> = chord.split('+').map(str::trim).collect();
+        let Some(key) = names.pop().filter(|key| !key.is_empty()) else {
+            warn!("Key chord {:?} has no key", chord);
+            return
+        };
+        let mut args = Vec::new();
+        for modifier in names.iter() {
+            args.extend(["-M".to_string(), modifier.to_string()]);
+        }
+        args.extend(["-k".to_string(), key.to_string()]);
+        for modifier in names.iter().rev() {
+            args.extend(["-m".to_string(), modifier.to_string()]);
+        }
+        self.run_wtype(args);
+    }
+
+    /// Queues a wtype run, after anything sent before it.
+    fn run_wtype(&self, args: Vec<String>) {
+        if self.wtype.send(args).is_err() {
+            error!("Failed to type, the wtype thread has stopped");
+        }
+    }
+}
+
+
+/// Runs wtype with each list of arguments sent, one run after another on its own thread.
+/// The ui doesn't wait for wtype, and the text still arrives in the order it was sent.
+fn spawn_wtype() -> Sender<Vec<String>> {
+    let (sender, receiver) = mpsc::channel::<Vec<String>>();
+    std::thread::spawn(move || {
+        for args in receiver {
+            if let Err(err) = std::process::Command::new("wtype").args(&args).status() {
+                error!("Failed to run wtype {:?}, with error: {}", args, err);
+            }
+        }
+    });
+    sender
+}
+
+/// Applies the typing rules to text typed after the text before it.
+/// Returns how many characters to delete and the text to type instead.
+fn apply_rules(rules: &TypingRules, before: &str, delete: usize, text: &str) -> (usize, String) {
+    let mut chars = before.chars().rev();
+    let (last, second_last) = (chars.next(), chars.next());
+    let after_word_space = last == Some(' ') && second_last.is_some_and(|c| c.is_alphanumeric());
+
+    // a second space after a word ends the sentence
+    if rules.double_space_period && text == " " && after_word_space {
+        return (delete + 1, String::from(". "))
+    }
+
+    // punctuation goes straight after the word
+    if rules.smart_punctuation && text.starts_with(SMART_PUNCTUATION) && after_word_space {
+        return (delete + 1, text.to_string())
+    }
+
+    match rules.auto_capitalize && is_sentence_start(before) {
+        true => (delete, capitalize(text)),
+        false => (delete, text.to_string()),
+    }
+}
+
+/// The text left after pressing backspace delete times.
+/// Deletes are counted in chars everywhere, as apps delete a char for each backspace,
+/// even from a grapheme such as an accent written with a combining mark, or a conjunct.
+fn text_before(text: &str, delete: usize) -> &str {
+    if delete == 0 {
+        return text
+    }
+    match text.char_indices().rev().nth(delete - 1) {
+        Some((index, _)) => &text[..index],
+        None => "",
+    }
+}
+
+/// The text left after deleting the last words, with the spaces after them.
+fn words_before(text: &str, count: usize) -> &str {
+    let mut remaining = text;
+    for _ in 0..count {
+        let trimmed = remaining.trim_end();
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(index, c)| index + c.len_utf8());
+        remaining = &trimmed[..start];
+    }
+    remaining
+}
+
+/// True if a word typed after the text starts a sentence.
+fn is_sentence_start(text: &str) -> bool {
+    let trimmed = text.trim_end();
+    trimmed.is_empty() || (trimmed.len() < text.len() && trimmed.ends_with(SENTENCE_END))
+}
+
+/// The text in the case of the text it replaces, all capitals if that was, otherwise a capital first if that had one.
+fn match_case(replaced: &str, text: &str) -> String {
+    let letters: Vec<char> = replaced.chars().filter(|c| c.is_alphabetic()).collect();
+    match letters.first() {
+        Some(_) if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) => text.to_uppercase(),
+        Some(first) if first.is_uppercase() => capitalize(text),
+        _ => text.to_string(),
+    }
+}
+
+/// Upper cases the first letter.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_before_deletes_the_last_words() {
+        assert_eq!(words_before("hello big world", 1), "hello big ");
+        assert_eq!(words_before("hello big world", 2), "hello ");
+        assert_eq!(words_before("hello big world", 3), "");
+    }
+
+    #[test]
+    fn words_before_deletes_the_spaces_after_a_word() {
+        assert_eq!(words_before("hello world  ", 1), "hello ");
+        assert_eq!(words_before("hello world. ", 1), "hello ");
+        assert_eq!(words_before("one\ttwo\nthree", 2), "one\t");
+    }
+
+    #[test]
+    fn words_before_stops_at_the_start() {
+        assert_eq!(words_before("hello world", 5), "");
+        assert_eq!(words_before("", 1), "");
+        assert_eq!(words_before("   ", 1), "");
+    }
+
+    #[test]
+    fn words_before_keeps_the_text_for_no_words() {
+        assert_eq!(words_before("hello world ", 0), "hello world ");
+    }
+
+    #[test]
+    fn text_before_deletes_a_committed_word_by_its_chars() {
+        // an accent as a combining mark, and a Devanagari conjunct, are several chars in one grapheme
+        for word in ["hello", "cafe\u{301}", "क्षमा"] {
+            let typed = format!(" {word}");
+            let text = format!("say{typed}");
+            assert_eq!(text_before(&text, typed.chars().count()), "say");
+        }
+        assert_eq!(text_before("say", 0), "say");
+        assert_eq!(text_before("say", 5), "");
+    }
+
+    #[test]
+    fn words_before_handles_multibyte_characters() {
+        assert_eq!(words_before("café crème brûlée", 1), "café crème ");
+        assert_eq!(words_before("naïve\u{3000}日本語", 1), "naïve\u{3000}");
+    }
+}
@@ -0,0 +1,261 @@
+use iced::{
+    time::Instant,
+    Point,
+    Rectangle
+};
+use std::io;
+use std::path::{
+    Path,
+    PathBuf
+};
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH
+};
+use super::*;
+
+static HEADER: &str = "# surfboard glide 1";
+pub static RECORDING_DIR: &str = "recordings"; // in the xdg data home
+pub static RECORDING_EXTENSION: &str = "glide";
+
+/// A recorded glide, with the key layout it was made on and the word the user meant.
+/// The word is empty for a glide the user never labelled, eg. when they moved on without typing the word.
+/// Stored as text, one item per line:
+/// ```text
+/// # surfboard glide 1
+/// word hello
+/// context say
+/// key h 270 62.5 60 62.5
+/// point 0 301.5 90.25
+/// ```
+/// Keys are `label x y width height`, points are `milliseconds x y`. Unlabelled glides have no word line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlideRecording {
+    pub word: String,
+    pub context: Vec<String>, // words committed before the glide
+    pub keys: Vec<(String, Rectangle)>,
+    pub points: Vec<(u32, Point)>, // milliseconds since the glide started
+}
+
+impl GlideRecording {
+    pub fn is_labelled(&self) -> bool {
+        !self.word.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\n");
+        if self.is_labelled() {
+            text.push_str(&format!("word {}\n", self.word));
+        }
+        if !self.context.is_empty() {
+            text.push_str(&format!("context {}\n", self.context.join(" ")));
+        }
+        for (label, bounds) in self.keys.iter() {
+            text.push_str(&format!("key {} {} {} {} {}\n", label, bounds.x, bounds.y, bounds.width, bounds.height));
+        }
+        for (time, point) in self.points.iter() {
+            text.push_str(&format!("point {} {} {}\n", time, point.x, point.y));
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(String::from("not a glide recording"))
+        }
+
+        let mut recording = GlideRecording::default();
+        for line in lines {
+            let (kind, values) = line.split_once(' ').unwrap_or((line, ""));
+            match kind {
+                "word" => recording.word = values.to_string(),
+                "context" => recording.context = values.split_whitespace().map(|word| word.to_string()).collect(),
+                "key" => {
+                    // labels can contain spaces, so the bounds are read from the end
+                    let fields: Vec<&str> = values.rsplitn(5, ' ').collect();
+                    let [height, width, y, x, label] = fields[..] else {
+                        return Err(format!("malformed key line: {line:?}"))
+                    };
+                    let (Ok(x), Ok(y), Ok(width), Ok(height)) = (x.parse(), y.parse(), width.parse(), height.parse()) else {
+                        return Err(format!("malformed key line: {line:?}"))
+                    };
+                    recording.keys.push((label.to_string(), Rectangle { x, y, width, height }));
+                }
+                "point" => {
+                    let fields: Vec<&str> = values.split_whitespace().collect();
+                    let [time, x, y] = fields[..] else {
+                        return Err(format!("malformed point line: {line:?}"))
+                    };
+                    let (Ok(time), Ok(x), Ok(y)) = (time.parse(), x.parse(), y.parse()) else {
+                        return Err(format!("malformed point line: {line:?}"))
+                    };
+                    recording.points.push((time, Point::new(x, y)));
+                }
+                "" => {}
+                _ => return Err(format!("unknown line: {line:?}")),
+            }
+        }
+        Ok(recording)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_text(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+}
+
+
+/// Records glides while enabled, so they can be replayed with `surfboard bench`.
+/// A glide that ends is kept until the next word is committed, which labels and saves it,
+/// whether the word was picked from the suggestions, tapped, or autocorrected.
+/// A glide that is never labelled is still saved, without a word, when the next glide ends.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    enabled: bool,
+    started: Option<Instant>,
+    recording: Option<GlideRecording>, // the glide in progress
+    pending: Option<GlideRecording>, // the last glide, waiting for its word
+    last_saved: Option<(PathBuf, GlideRecording)>, // the glide the last committed word labelled, so it can be corrected
+}
+
+impl Recorder {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        info!("Glide recording: {}", enabled);
+        if let Some(pending) = self.pending.take() {
+            save(pending);
+        }
+        self.enabled = enabled;
+        self.started = None;
+        self.recording = None;
+        self.last_saved = None;
+    }
+
+    /// Starts recording a glide, the last glide is kept until it is labelled.
+    pub fn start(&mut self, keys: &[Candidate], context: &[String]) {
+        if !self.enabled {
+            return
+        }
+
+        // keys report their bounds on every layout, keep the latest for each label
+        let mut recording = GlideRecording::default();
+        for key in keys.iter().rev() {
+            if !recording.keys.iter().any(|(label, _)| *label == key.text) {
+                recording.keys.push((key.text.clone(), key.bounds));
+            }
+        }
+        recording.keys.reverse();
+        recording.context = context.to_vec();
+
+        self.started = Some(Instant::now());
+        self.recording = Some(recording);
+    }
+
+    /// Drops the glide being recorded, eg. when it wasn't a word.
+    pub fn cancel(&mut self) {
+        self.started = None;
+        self.recording = None;
+    }
+
+    pub fn add_point(&mut self, point: Point) {
+        let (Some(started), Some(recording)) = (self.started, self.recording.as_mut()) else {
+            return
+        };
+        recording.points.push((started.elapsed().as_millis() as u32, point));
+    }
+
+    /// Ends the glide being recorded, so the next committed word labels it.
+    /// The last glide was never labelled, so it is saved without a word.
+    pub fn end(&mut self) {
+        self.started = None;
+        let Some(recording) = self.recording.take().filter(|recording| !recording.points.is_empty()) else {
+            return
+        };
+        if let Some(pending) = self.pending.replace(recording) {
+            save(pending);
+        }
+    }
+
+    /// Labels the last glide with the word the user meant and saves it.
+    pub fn label(&mut self, word: &str) {
+        let Some(mut recording) = self.pending.take() else {
+            self.last_saved = None;
+            return
+        };
+        recording.word = word.to_string();
+        self.last_saved = save(recording.clone()).map(|path| (path, recording));
+    }
+
+    /// Changes the label of the last labelled glide, eg. when an autocorrected word is put back as it was typed.
+    pub fn relabel(&mut self, word: &str) {
+        let Some((path, recording)) = self.last_saved.as_mut() else {
+            return
+        };
+        recording.word = word.to_string();
+        match recording.save(path) {
+            Ok(_) => info!("Relabelled glide \"{}\" in {}", word, path.display()),
+            Err(err) => error!("Failed to save glide \"{}\", with error: {}", path.display(), err),
+        }
+    }
+}
+
+
+/// Saves the recording to a new file in the recordings dir, returning its path.
+fn save(recording: GlideRecording) -> Option<PathBuf> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_millis()).unwrap_or_default();
+    let name = format!("{RECORDING_DIR}/{now}.{RECORDING_EXTENSION}");
+    let path = match xdg::BaseDirectories::with_prefix("surfboard").map(|dirs| dirs.place_data_file(&name)) {
+        Ok(Ok(path)) => path,
+        _ => {
+            error!("Unable to find the xdg data dir, the glide will not be saved");
+            return None
+        }
+    };
+    match recording.save(&path) {
+        Ok(_) => {
+            match recording.is_labelled() {
+                true => info!("Recorded glide \"{}\" to {}", recording.word, path.display()),
+                false => info!("Recorded unlabelled glide to {}", path.display()),
+            }
+            Some(path)
+        }
+        Err(err) => {
+            error!("Failed to save glide \"{}\", with error: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// The directory recordings are saved to, if there is an xdg data home.
+pub fn recording_dir() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("surfboard")
+        .ok()
+        .map(|dirs| dirs.get_data_home().join(RECORDING_DIR))
+}
+
+/// Loads every recording in the directory, sorted by file name, skipping any that can't be read.
+pub fn load_recordings(dir: &Path) -> io::Result<Vec<(PathBuf, GlideRecording)>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == RECORDING_EXTENSION))
+        .collect();
+    paths.sort();
+
+    let mut recordings = Vec::new();
+    for path in paths {
+        match GlideRecording::load(&path) {
+            Ok(recording) => recordings.push((path, recording)),
+            Err(err) => warn!("Skipping glide \"{}\", with error: {}", path.display(), err),
+        }
+    }
+    Ok(recordings)
+}
@@ -1,21 +1,58 @@
 use iced::{
+    border,
+    widget::{
+        container,
+        row,
+        text
+    },
+    Element,
+    Length,
+    Padding,
     Point, 
     Rectangle, 
-    Task
+    Size,
+    Subscription,
+    Task,
+    time::{
+        self,
+        Duration,
+        Instant
+    }
 };
-use trie_rs::Trie;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 use crate::utils::*;
 use crate::app::*;
-use trie_rs::{TrieBuilder, inc_search::Answer};
+use crate::lang::*;
+use crate::comp::*;
+use crate::utils::config::SearchParams;
 
-static MIN_DISTANCE: f32 = 15.0; // pixels
+static MAX_SUGGESTIONS: usize = 5;
+static MAX_CONTEXT: usize = 2; // previous words used by the language model
+static HISTORY_WEIGHT: f32 = 0.3; // share of the probability given to the users own history
+pub static SUGGESTION_HEIGHT: f32 = 40.0; // pixels
+static PREVIEW_OFFSET: f32 = 60.0; // pixels above the finger
+static TAP_LANGUAGE_WEIGHT: f32 = 0.5; // tap cost per log10 of language model probability
+static DELETE_WORD_KEYS: f32 = 1.5; // key widths swiped left from backspace for each extra word deleted
+static SAVE_DELAY: Duration = Duration::from_secs(10); // learning is saved this long after it changes, and on exit
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Update(String, Rectangle),
     Reset,
+    Pick(String),
+    Forget(String),
+    NextLanguage,
+    SetLanguage(String),
+    SetLayout(String), // id of the view the keys are on, key targets are learned per layout
+    ToggleLanguage(String), // enable or disable a language in the settings
+    ToggleRecording,
+    UndoCorrection, // restore the word as it was tapped, before it was autocorrected
+    Backspace,
+    Write(Vec<(String, f32)>), // a handwritten character, the likely characters best first
+    ActionGesture(ActionDirection, Option<Point>), // a quick swipe or long press, and where it started
+    Reopen(String), // the output deleted the last committed word, show its alternatives again
+    Save, // writes what has been learned, see `SAVE_DELAY`
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -29,34 +66,58 @@ pub struct Candidate {
     pub first_or_last_weight: bool,
     pub angles_change_weight: u32,
     pub is_complete: bool,
+    pub entered: Option<Instant>, // time of the first point over the key
+    pub dwell: u32, // ms between the first and last point over the key
+    pub min_speed: f32, // slowest pixels per ms over the key
+    pub turning: f32, // degrees the path turned over the key
+    pub target: Point, // where the user aims for the key, the bounds center until it is learned
 }
 
 impl Candidate {
-    pub fn add_point(&mut self, point: Point) {
+    /// Adds a point over the key, at the time and speed the finger was moving.
+    pub fn add_point(&mut self, point: Point, instant: Instant, speed: Option<f32>, params: &SearchParams) {
         self.points.push(point);
 
+        // timing, the finger slows down or pauses on keys that are meant
+        let entered = *self.entered.get_or_insert(instant);
+        self.dwell = instant.duration_since(entered).as_millis() as u32;
+        if let Some(speed) = speed {
+            self.min_speed = self.min_speed.min(speed);
+        }
+
         if self.filtered_points.len() > 1 {
             // distance check with the back/end item
             let prev = self.filtered_points.last().unwrap();
             let distance = Point::distance(&prev, point);
-            if distance < MIN_DISTANCE {
+            if distance < params.min_distance {
                 return
             }
             self.filtered_points.push(point);
+
+            // add up the turns, going around a small loop is close to 360 degrees
+            let length = self.filtered_points.len();
+            if length > 2 {
+                let angle = functions::angle_between_points(
+                    self.filtered_points[length - 3],
+                    self.filtered_points[length - 2],
+                    self.filtered_points[length - 1],
+                );
+                self.turning += if angle > 180.0 { 360.0 - angle } else { angle };
+            }
         } else {
             self.filtered_points.push(point);
         }
     }
 
-    pub fn update_weight(&mut self) -> bool {
+    pub fn update_weight(&mut self, params: &SearchParams) -> bool {
         let mut new_weight = 0;
 
         // weight distance between the last point and the center of the bounds
         if self.points.len() > 1 {
             let falloff = (functions::gaussian_pdf(
                 self.points[self.points.len() - 1], 
-                self.bounds.center(), 
-                15.0) * 100.0
+                self.target, 
+                params.sigma) * 100.0
             ) as u32;
             if falloff > self.position_weight {
                 self.position_weight = falloff;
@@ -84,9 +145,17 @@ impl Candidate {
             new_weight += self.angles_change_weight;
         }
 
+        // weight time spent over the key
+        new_weight += (self.dwell.min(params.max_dwell) as f32 * params.dwell_weight) as u32;
+
+        // weight slowing down over the key
+        if self.min_speed < params.slow_speed {
+            new_weight += params.slow_weight;
+        }
+
         // weight first and last points
         if self.first_or_last_weight {
-            new_weight += 200;
+            new_weight += params.edge_weight;
         }
         
         // update the weight if it has changed
@@ -96,15 +165,55 @@ impl Candidate {
         }
         false
     }
+
+    /// A pause or a small loop over the key, which is how a double letter is glided.
+    pub fn is_double(&self, params: &SearchParams) -> bool {
+        self.dwell >= params.double_dwell || self.turning >= params.double_turning
+    }
+}
+
+/// A tapped word that was autocorrected when the separator after it was tapped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correction {
+    pub original: String,
+    pub corrected: String,
+    pub separator: String,
+}
+
+/// A ranked dictionary word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub text: String,
+    pub score: f32,
 }
 
 /// Handles the state of widget/components.  
-/// This is used for the glide typing.
+/// This is used for the glide and tap typing.
 #[derive(Clone, Debug)]
 pub struct SearchHandler {
     components: Vec<Candidate>,
     weighted_items: Vec<Candidate>,
-    dictionary: Option<Trie<u8>>,
+    dictionary: Option<WordTrie>,
+    decoder: Decoder, // partial words for the current glide
+    taps: TapDecoder, // letters tapped since the last word ended
+    correction: Option<Correction>, // the last tapped word, if it was autocorrected
+    last_glide: Option<Vec<Suggestion>>, // ranked words of the last glide, while nothing else has been typed
+    targets: KeyTargets, // where the user taps each key, for this layout and window size
+    layout: String, // id of the view the keys are on
+    window_size: Option<Size>,
+    user_dictionary: Option<UserDictionary>,
+    languages: Vec<Language>, // every language with a dictionary installed
+    language: String, // tag of the active language
+    language_model: Option<NgramModel>,
+    context: Vec<String>, // previously committed words, most recent last
+    history: NgramModel, // what the user has committed this session
+    suggestions: Vec<Suggestion>,
+    recorder: Recorder, // records glides for `surfboard bench`
+    words_changed: bool, // the user dictionary has changes that aren't saved yet
+    targets_changed: bool, // the key targets have changes that aren't saved yet
+    params: SearchParams,
+    preview: Option<(String, Point)>, // best word so far, shown at the finger while gliding
+    last_move: Option<(Point, Instant)>, // previous point of the glide, for the speed
 }
 
 impl SearchHandler {
@@ -113,6 +222,26 @@ impl SearchHandler {
             components: Vec::new(),
             weighted_items: Vec::new(),
             dictionary: None,
+            decoder: Decoder::default(),
+            taps: TapDecoder::default(),
+            correction: None,
+            last_glide: None,
+            targets: KeyTargets::default(),
+            layout: View::CompactQwerty.id().to_string(), // the view handler starts on compact qwerty
+            window_size: None,
+            user_dictionary: None,
+            languages: Vec::new(),
+            language: config::get().languages.first().cloned().unwrap_or(BUILTIN_LANGUAGE.to_string()),
+            language_model: None,
+            context: Vec::new(),
+            history: NgramModel::default(),
+            suggestions: Vec::new(),
+            recorder: Recorder::default(),
+            words_changed: false,
+            targets_changed: false,
+            params: config::get().search.clone(),
+            preview: None,
+            last_move: None,
         }
     }
 
@@ -128,40 +257,639 @@ impl SearchHandler {
                     position_weight: 0, 
                     first_or_last_weight: false,
                     angles_change_weight: 0,
-                    is_complete: false
+                    is_complete: false,
+                    entered: None,
+                    dwell: 0,
+                    min_speed: f32::MAX,
+                    turning: 0.0,
+                    target: rectangle.center(),
                 });
                 Task::none()
             }
             Message::Reset => {
                 self.components.clear();
                 self.weighted_items.clear();
+                self.taps.reset();
+                Task::none()
+            }
+            Message::Pick(word) => self.commit(word, true),
+            Message::Forget(word) => {
+                if self.user_dictionary.as_mut().is_some_and(|user_dictionary| user_dictionary.remove(&word)) {
+                    info!("Forgot word: {}", word);
+                    self.save_user_dictionary();
+                    // the learned words are shared by every language, so the word can be in this language's word list,
+                    // rebuilding keeps it then
+                    self.reload_dictionary();
+                }
+                self.learned_words()
+            }
+            Message::NextLanguage => {
+                // cycle through the enabled languages that are installed
+                let enabled: Vec<String> = config::get().languages
+                    .iter()
+                    .filter(|tag| self.languages.iter().any(|language| &language.tag == *tag))
+                    .cloned()
+                    .collect();
+                let Some(next) = enabled
+                    .iter()
+                    .position(|tag| *tag == self.language)
+                    .map_or(enabled.first(), |index| enabled.get((index + 1) % enabled.len())) else {
+                    return Task::none()
+                };
+                let next = next.clone();
+
+                // swap to the layout that uses this language by default
+                let layout = config::get().layout_languages
+                    .iter()
+                    .find(|(_, tag)| **tag == next)
+                    .and_then(|(view, _)| View::from_id(view));
+
+                let mut tasks = vec![self.set_language(next)];
+                if let Some(view) = layout {
+                    tasks.push(Task::done(view::Message::ChangeView(view)).map(main_app::Message::ViewHandler));
+                }
+                Task::batch(tasks)
+            }
+            Message::SetLanguage(tag) => self.set_language(tag),
+            Message::SetLayout(layout) => {
+                if layout != self.layout {
+                    self.layout = layout;
+                    self.load_targets();
+                }
                 Task::none()
             }
+            Message::ToggleRecording => {
+                self.recorder.set_enabled(!self.recorder.is_enabled());
+                Task::done(view::Message::Recording(self.recorder.is_enabled())).map(main_app::Message::ViewHandler)
+            }
+            Message::Backspace => self.backspace(),
+            Message::Write(candidates) => self.write(candidates),
+            Message::ActionGesture(direction, start) => {
+                let region = start.and_then(|position| self.region_at(position));
+                Task::done(view::Message::ActionGesture(direction, region)).map(main_app::Message::ViewHandler)
+            }
+            Message::Reopen(word) => {
+                if self.context.last().is_some_and(|last| *last == word.to_lowercase()) {
+                    self.context.pop();
+                }
+
+                // the word was wrong, so its alternatives are offered in the order they were ranked
+                let alternatives: Vec<Suggestion> = self.last_glide
+                    .iter()
+                    .flatten()
+                    .filter(|suggestion| suggestion.text != word)
+                    .cloned()
+                    .collect();
+                match alternatives.is_empty() {
+                    true => self.predict(),
+                    false => self.suggestions = alternatives,
+                }
+                Task::none()
+            }
+            Message::UndoCorrection => {
+                let Some(correction) = self.correction.take() else {
+                    return Task::none()
+                };
+                info!("Undo correction: {} -> {}", correction.corrected, correction.original);
+
+                // the separator was typed after the corrected word
+                let typed = correction.corrected.chars().count() + correction.separator.chars().count();
+                self.recorder.relabel(&correction.original);
+                self.context.pop();
+                self.push_context(&correction.original);
+                self.predict();
+
+                // keeping the word is a sign it is meant, so learn it straight away
+                Task::batch(vec![
+                    Task::done(output::Message::Replace(typed, format!("{}{}", correction.original, correction.separator)))
+                        .map(main_app::Message::OutputHandler),
+                    self.learn_word(&correction.original, true),
+                ])
+            }
+            Message::Save => {
+                self.save();
+                Task::none()
+            }
+            Message::ToggleLanguage(tag) => {
+                config::update(|config| {
+                    match config.languages.iter().position(|item| *item == tag) {
+                        Some(index) => { config.languages.remove(index); }
+                        None => config.languages.push(tag),
+                    }
+                });
+                self.languages()
+            }
+        }
+    }
+
+    /// Switches the dictionary and language model to the language with the tag.
+    pub fn set_language(&mut self, tag: String) -> Task<main_app::Message> {
+        if tag == self.language {
+            return Task::none()
+        }
+        info!("Language: {}", tag);
+        self.language = tag;
+        self.language_model = None;
+        self.reload_dictionary();
+        self.predict();
+        self.languages()
+    }
+
+    /// Sets the size of the window, key targets are learned per window size as the keys move and scale with it.
+    pub fn set_window_size(&mut self, size: Size) {
+        if self.window_size == Some(size) {
+            return
+        }
+        self.window_size = Some(size);
+        self.load_targets();
+    }
+
+    /// Loads the key targets for the layout and window size, saving the ones they replace.
+    fn load_targets(&mut self) {
+        let Some(size) = self.window_size else {
+            return
+        };
+        self.save_targets();
+        self.targets = KeyTargets::load(&self.layout, size);
+    }
+
+    fn save_targets(&mut self) {
+        if !self.targets_changed {
+            return
         }
+        self.targets_changed = false;
+        if let Err(err) = self.targets.save() {
+            error!("Failed to save key targets, with error: {}", err);
+        }
+    }
+
+    /// Learns where the user taps each key from the letters of a tapped word.
+    /// Only words with a tap for each letter are used, so every tap is matched to the letter it was meant for.
+    fn learn_taps(&mut self, word: &str) {
+        let taps = self.taps.taps();
+        if taps.is_empty() || unicode::graphemes(word).count() != taps.len() {
+            return
+        }
+        for (letter, tap) in unicode::graphemes(word).zip(taps) {
+            let Some(point) = tap.point else {
+                continue
+            };
+            // keys report their bounds on every layout, the latest is last
+            let letters = Some(unicode::fold(letter));
+            let Some(key) = self.components.iter().rev().find(|component| unicode::key_letters(&component.text) == letters) else {
+                continue
+            };
+            self.targets.learn(&key.text, point - key.bounds.center());
+            self.targets_changed = true;
+        }
+    }
+
+    /// Sends the installed languages and the active one to the views.
+    pub fn languages(&self) -> Task<main_app::Message> {
+        let tags: Vec<String> = self.languages.iter().map(|language| language.tag.clone()).collect();
+        Task::done(view::Message::Languages(tags, self.language.clone())).map(main_app::Message::ViewHandler)
+    }
+
+    /// The suggestion bar, showing glide candidates while gliding and next word predictions otherwise.
+    pub fn view(&self) -> Element<main_app::Message> {
+        // after an autocorrection, the tapped word comes first so it can be put back
+        let undo = self.correction
+            .iter()
+            .map(|correction| Element::from(key_undo_correction(&correction.original)));
+        let keys: Vec<Element<main_app::Message>> = undo
+            .chain(self.suggestions
+                .iter()
+                .map(|suggestion| Element::from(key_suggestion(&suggestion.text))))
+            .collect();
+
+        row(keys)
+            .width(Length::Fill)
+            .height(Length::Fixed(SUGGESTION_HEIGHT))
+            .into()
+    }
+
+    /// The best word so far, floating above the finger while gliding.
+    pub fn preview_view(&self) -> Element<main_app::Message> {
+        let Some((word, position)) = self.preview.as_ref() else {
+            return row![].into()
+        };
+
+        let bubble = container(text(word.clone()).size(24))
+            .padding(8)
+            .style(|_theme| {
+                let theme = theme::get();
+                container::Style {
+                    background: Some(theme.preview.into()),
+                    text_color: Some(theme.text),
+                    border: border::rounded(theme.corner_radius),
+                    ..container::Style::default()
+                }
+            });
+
+        // placed by padding it out from the top left of the window
+        container(bubble)
+            .padding(Padding {
+                top: (position.y - PREVIEW_OFFSET).max(0.0),
+                right: 0.0,
+                bottom: 0.0,
+                left: (position.x - PREVIEW_OFFSET / 2.0).max(0.0),
+            })
+            .into()
     }
 
     pub fn start(&mut self) -> Task<main_app::Message> {
         self.weighted_items.clear();
+        self.decoder.reset();
+        self.taps.reset(); // letters tapped before the glide are kept as they were typed
+        self.correction = None;
+        self.preview = None;
+        self.last_move = None;
+        self.recorder.start(&self.components, &self.context);
         Task::none()
     }
 
     pub fn end(&mut self) -> Task<main_app::Message> {
-        // update last item
-        let last = self.weighted_items.last_mut().unwrap();
-        last.first_or_last_weight = true;
-        last.update_weight();
-        self.search_word();
+        if let Some(task) = self.swipe_command() {
+            return task
+        }
+
+        if !self.finish() {
+            return Task::none()
+        }
 
         // log
         let formatted_items: String = self.weighted_items
             .iter()
             .map(|item| format!("{} - {}\n", item.text, item.weight))
             .collect();
-        info!("weighted:\n{}", formatted_items);
-        Task::none()
+        debug!("weighted:\n{}", formatted_items);
+
+        // when recording, the next word the user commits labels the glide, picked or typed
+        if self.recorder.is_enabled() {
+            self.recorder.end();
+            return Task::none()
+        }
+
+        // commit the best match, keeping the others in case it is deleted
+        self.last_glide = Some(self.suggestions.clone());
+        match self.suggestions.first() {
+            Some(best) => self.commit(best.text.clone(), false),
+            None => Task::none(),
+        }
+    }
+
+    /// Swipes that start on backspace or the space bar are commands rather than words.
+    /// Swiping left from backspace deletes words, more the further it goes,
+    /// and swiping right along the space bar accepts the top suggestion.
+    fn swipe_command(&mut self) -> Option<Task<main_app::Message>> {
+        let first = self.weighted_items.first()?;
+        let start = *first.points.first()?;
+        let (end, _) = self.last_move?;
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        if dx.abs() <= dy.abs() {
+            return None
+        }
+
+        let task = match first.text.as_str() {
+            label if label == BACKSPACE && dx < 0.0 => {
+                self.recorder.cancel();
+                let count = 1 + (-dx / (first.bounds.width * DELETE_WORD_KEYS)) as usize;
+                info!("Swipe delete {} words", count);
+                for _ in 0..count.min(self.context.len()) {
+                    self.context.pop();
+                }
+                self.taps.reset();
+                self.correction = None;
+                self.last_glide = None;
+                self.predict();
+                Task::done(output::Message::DeleteWords(count)).map(main_app::Message::OutputHandler)
+            }
+            label if label.trim().is_empty() && dx > 0.0 => {
+                let best = self.suggestions.first()?.text.clone();
+                info!("Swipe accept: {}", best);
+                // the swipe isn't a glide, the word it accepts labels the glide before it
+                self.recorder.cancel();
+                self.commit(best, true)
+            }
+            _ => return None,
+        };
+        self.preview = None;
+        self.weighted_items.clear();
+        Some(task)
+    }
+
+    /// True if the position is above the keys, eg. on the suggestion bar.
+    pub fn is_above_keys(&self, position: Point) -> bool {
+        self.components
+            .iter()
+            .map(|component| component.bounds.y)
+            .reduce(f32::min)
+            .is_some_and(|top| position.y < top)
+    }
+
+    /// Drops the glide without typing anything, eg. when it was a drawn shape.
+    pub fn cancel(&mut self) {
+        self.weighted_items.clear();
+        self.decoder.reset();
+        self.preview = None;
+        self.recorder.cancel();
+    }
+
+    /// Scores the glide that has just ended, filling the suggestions.
+    /// Returns false if nothing was glided over.
+    pub fn finish(&mut self) -> bool {
+        self.preview = None;
+
+        // update last item
+        let Some(last) = self.weighted_items.last_mut() else {
+            return false
+        };
+        last.first_or_last_weight = true;
+        last.update_weight(&self.params);
+        self.search_word(true);
+        true
+    }
+
+    /// Replaces the scoring params, eg. while tuning them.
+    pub fn set_params(&mut self, params: SearchParams) {
+        self.params = params;
+    }
+
+    /// Ranked words for the current glide, or the next word predictions.
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
+
+    /// Sends the word to the output, learns from it and predicts the next word.
+    /// picked is true when the user chose the word from the suggestions.
+    pub fn commit(&mut self, word: String, picked: bool) -> Task<main_app::Message> {
+        // a word picked while tapping replaces the letters tapped so far
+        let output = match self.taps.is_empty() {
+            true => output::Message::Commit(word.clone()),
+            false => output::Message::Replace(self.taps.len(), word.clone()),
+        };
+        self.learn_taps(&word);
+        self.taps.reset();
+        self.correction = None;
+        // picking another word of the glide keeps its words, so it can be deleted and picked again
+        if !self.last_glide.iter().flatten().any(|suggestion| suggestion.text == word) {
+            self.last_glide = None;
+        }
+        Task::batch(vec![
+            Task::done(output).map(main_app::Message::OutputHandler),
+            self.accept(&word, picked),
+        ])
+    }
+
+    /// Learns from a word that has been typed and predicts the next word.
+    fn accept(&mut self, word: &str, picked: bool) -> Task<main_app::Message> {
+        self.recorder.label(word);
+        let learn_task = self.learn_word(word, picked);
+        self.history.learn(&self.context, word);
+        self.push_context(word);
+        self.predict();
+        learn_task
+    }
+
+    /// Types the letter on the tapped key, or ends the tapped word when a space or punctuation is tapped.
+    pub fn tap(&mut self, position: Point) -> Task<main_app::Message> {
+        // keys report their bounds on every layout, the latest is last
+        let Some(key) = self.components.iter().rev().find(|component| component.bounds.contains(position)) else {
+            return Task::none()
+        };
+        let label = key.text.clone();
+        self.last_glide = None;
+
+        if label == BACKSPACE {
+            return self.backspace()
+        }
+
+        if let Some(separator) = TapDecoder::separator(&label) {
+            return self.end_taps(separator)
+        }
+
+        let Some(key) = self.taps.push(position, &label, &self.components, &self.targets) else {
+            return Task::none()
+        };
+        self.correction = None;
+        self.search_taps();
+        Task::done(output::Message::Type(key)).map(main_app::Message::OutputHandler)
+    }
+
+    /// Name of the key at the position, as used for gesture actions, the space bar is "space".
+    fn region_at(&self, position: Point) -> Option<String> {
+        let key = self.components.iter().rev().find(|component| component.bounds.contains(position))?;
+        match key.text.trim() {
+            "" => Some(String::from("space")),
+            label => Some(label.to_string()),
+        }
+    }
+
+    /// Deletes the last tapped letter, otherwise the output deletes the last committed word,
+    /// or the character before the cursor if something else has been typed since.
+    pub fn backspace(&mut self) -> Task<main_app::Message> {
+        self.correction = None;
+        let Some(tap) = self.taps.pop() else {
+            return Task::done(output::Message::Backspace).map(main_app::Message::OutputHandler)
+        };
+
+        match self.taps.is_empty() {
+            true => self.predict(),
+            false => self.search_taps(),
+        }
+        Task::done(output::Message::Replace(tap.key.chars().count(), String::new())).map(main_app::Message::OutputHandler)
+    }
+
+    /// Types a handwritten character. Letters are read like taps, so the word is corrected when it ends,
+    /// other characters are typed as they are.
+    pub fn write(&mut self, candidates: Vec<(String, f32)>) -> Task<main_app::Message> {
+        let Some((best, _)) = candidates.first() else {
+            return Task::none()
+        };
+        let best = best.clone();
+        self.last_glide = None;
+        self.correction = None;
+        match self.taps.push_written(&best, &candidates) {
+            true => self.search_taps(),
+            false => self.taps.reset(),
+        }
+        Task::done(output::Message::Type(best)).map(main_app::Message::OutputHandler)
+    }
+
+    /// Ends the tapped word, autocorrecting it if it isn't in the dictionary, then types the separator.
+    fn end_taps(&mut self, separator: String) -> Task<main_app::Message> {
+        if self.taps.is_empty() {
+            // the typing rules can change the text before the separator, so the correction can't be undone
+            self.correction = None;
+            return Task::done(output::Message::Type(separator)).map(main_app::Message::OutputHandler)
+        }
+
+        let literal = self.taps.literal();
+        let is_word = self.dictionary.as_ref().is_some_and(|dictionary| dictionary.contains(&literal));
+        let word = match is_word {
+            true => literal.clone(),
+            false => self.suggestions.first().map_or(literal.clone(), |best| best.text.clone()),
+        };
+
+        let output = match word == literal {
+            true => output::Message::Type(separator.clone()),
+            false => {
+                debug!("Autocorrect: {} -> {}", literal, word);
+                self.correction = Some(Correction {
+                    original: literal.clone(),
+                    corrected: word.clone(),
+                    separator: separator.clone(),
+                });
+                output::Message::Replace(self.taps.len(), format!("{word}{separator}"))
+            }
+        };
+        self.learn_taps(&word);
+        self.taps.reset();
+
+        Task::batch(vec![
+            Task::done(output).map(main_app::Message::OutputHandler),
+            self.accept(&word, false),
+        ])
+    }
+
+    /// Ranks dictionary words against the letters tapped so far.
+    fn search_taps(&mut self) {
+        if self.dictionary.is_none() {
+            self.load_dictionary();
+        }
+
+        let dictionary = self.dictionary.as_ref().unwrap();
+        let mut suggestions: Vec<Suggestion> = self.taps
+            .words(dictionary)
+            .into_iter()
+            .map(|(word, tap_score)| {
+                let score = tap_score + self.log_prob(&word).map_or(0.0, |log_prob| TAP_LANGUAGE_WEIGHT * log_prob);
+                Suggestion { text: word, score }
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        self.suggestions = suggestions;
+
+        let formatted: Vec<&str> = self.suggestions.iter().map(|item| item.text.as_str()).collect();
+        debug!("tap result: {:?}", formatted);
+    }
+
+    /// Counts a use of a word the dictionary doesn't know.
+    /// Once it has been used enough it is added to the dictionary.
+    fn learn_word(&mut self, word: &str, picked: bool) -> Task<main_app::Message> {
+        if self.dictionary.as_ref().is_some_and(|dictionary| dictionary.contains(word)) {
+            return Task::none()
+        }
+        let Some(user_dictionary) = self.user_dictionary.as_mut() else {
+            return Task::none()
+        };
+
+        let learned = user_dictionary.learn(word, picked);
+        self.words_changed = true;
+        if !learned {
+            return Task::none()
+        }
+
+        info!("Learned word: {}", word);
+        if let Some(dictionary) = self.dictionary.as_mut() {
+            dictionary.insert(word);
+        }
+        self.learned_words()
+    }
+
+    /// Sends the learned words to the views, so they can be reviewed in the settings.
+    pub fn learned_words(&self) -> Task<main_app::Message> {
+        let words: Vec<LearnedWord> = self.user_dictionary
+            .iter()
+            .flat_map(|user_dictionary| user_dictionary.learned())
+            .cloned()
+            .collect();
+        Task::done(view::Message::LearnedWords(words)).map(main_app::Message::ViewHandler)
+    }
+
+    fn save_user_dictionary(&mut self) {
+        self.words_changed = false;
+        if let Some(user_dictionary) = self.user_dictionary.as_ref() {
+            if let Err(err) = user_dictionary.save() {
+                error!("Failed to save user dictionary, with error: {}", err);
+            }
+        }
+    }
+
+    /// Saves anything learned that isn't saved yet, eg. before exiting.
+    pub fn save(&mut self) {
+        if self.words_changed {
+            self.save_user_dictionary();
+        }
+        self.save_targets();
+    }
+
+    /// Saves what has been learned a while after it changes, rather than on every word.
+    pub fn subscription(&self) -> Subscription<Message> {
+        match self.words_changed || self.targets_changed {
+            true => time::every(SAVE_DELAY).map(|_| Message::Save),
+            false => Subscription::none(),
+        }
+    }
+
+    /// Fills the suggestions with the likely next words.
+    /// This runs when no gesture is active, eg. after a word is committed.
+    pub fn predict(&mut self) {
+        let mut words: Vec<String> = Vec::new();
+        if let Some(model) = self.language_model.as_ref() {
+            words.extend(model.predict(&self.context, MAX_SUGGESTIONS).into_iter().map(|(word, _)| word));
+        }
+        words.extend(self.history.predict(&self.context, MAX_SUGGESTIONS).into_iter().map(|(word, _)| word));
+        words.sort();
+        words.dedup();
+
+        let mut suggestions: Vec<Suggestion> = words
+            .into_iter()
+            .map(|text| {
+                let score = self.log_prob(&text).unwrap_or_default();
+                Suggestion { text, score }
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        self.suggestions = suggestions;
+    }
+
+    /// Replaces the language model context, eg. when replaying a recorded glide.
+    pub fn set_context(&mut self, context: &[String]) {
+        self.context.clear();
+        for word in context.iter() {
+            self.push_context(word);
+        }
+    }
+
+    /// Previously committed words, most recent last.
+    pub fn context(&self) -> &[String] {
+        &self.context
+    }
+
+    /// Adds a committed word to the language model context.
+    pub fn push_context(&mut self, word: &str) {
+        self.context.push(word.to_lowercase());
+        if self.context.len() > MAX_CONTEXT {
+            self.context.remove(0);
+        }
     }
 
     pub fn update_move(&mut self, position: Point) -> Task<main_app::Message> {
+        self.update_move_at(position, Instant::now())
+    }
+
+    /// Moves the glide to the position, at the time the point was made.
+    pub fn update_move_at(&mut self, position: Point, instant: Instant) -> Task<main_app::Message> {
+        // pixels per ms since the previous point
+        let speed = self.last_move.and_then(|(last_position, last_instant)| {
+            let elapsed = instant.duration_since(last_instant).as_secs_f32() * 1000.0;
+            (elapsed > 0.0).then(|| last_position.distance(position) / elapsed)
+        });
+        self.last_move = Some((position, instant));
 
         // loop through items, and find the one that contains the position
         let mut selected_component = None;
@@ -172,13 +900,16 @@ impl SearchHandler {
             }
         }
 
+        self.recorder.add_point(position);
+
         // update the weighted item
         if let Some(component) = selected_component {
 
-            match self.weighted_items.last_mut() {
+            let entered = match self.weighted_items.last_mut() {
                 Some(last_item) if last_item.text == component.text => {
-                    last_item.add_point(position);
-                    last_item.update_weight();
+                    last_item.add_point(position, instant, speed, &self.params);
+                    last_item.update_weight(&self.params);
+                    false
                 }
                 _ => {
                     // update the previous item and mark as complete
@@ -186,96 +917,84 @@ impl SearchHandler {
                         let length = self.weighted_items.len();
                         let previous_item = self.weighted_items.get_mut( length - 2).unwrap();
                         previous_item.is_complete = true;
-                        previous_item.update_weight();
+                        previous_item.update_weight(&self.params);
                     }
 
                     // mouse over new item or
                     // first item in the array
                     let mut new_item = component.clone();
-                    new_item.add_point(position);
+                    new_item.target = self.targets.center(&new_item.text, new_item.bounds);
+                    new_item.add_point(position, instant, speed, &self.params);
 
                     // first item
                     if self.weighted_items.is_empty() { 
                         new_item.first_or_last_weight = true
                     }
 
-                    new_item.update_weight();
+                    new_item.update_weight(&self.params);
                     self.weighted_items.push(new_item);
+                    true
                 }
-            }
+            };
 
-            // only search when we first enter the key, and when we exit the key
-            // the weight is always changing, so we dont want to keep searching
-            self.search_word();
+            // rank again only when a key is entered, the whole path is ranked when the glide ends
+            if entered {
+                self.search_word(false);
+            }
+            self.preview = self.suggestions.first().map(|best| (best.text.clone(), position));
         }
 
         Task::none()
     }
 
-    pub fn search_word(&mut self) {
+    /// Ranks dictionary words against the current key path.
+    /// When complete is true the gesture has ended, so the word must also end on the last key.
+    pub fn search_word(&mut self, complete: bool) {
         if self.dictionary.is_none() {
             self.load_dictionary();
         }
 
-        //info!("searching...");
-        // todo investigate: For unicode its easier to use .query_until().
+        // follow the dictionary along any keys entered since the last search
         let dictionary = self.dictionary.as_ref().unwrap();
-        let mut search = dictionary.inc_search();
-        let mut search_answer = String::new();
+        self.decoder.update(dictionary, &self.weighted_items);
 
-        for item in &self.weighted_items {
-            // skip low weight items
-            if item.weight < 100 {
-                continue
-            }
+        let mut suggestions: Vec<Suggestion> = self.decoder
+            .words(dictionary, &self.weighted_items, complete, &self.params)
+            .into_iter()
+            .map(|(word, path_score)| {
+                let score = path_score + self.language_score(&word);
+                Suggestion { text: word, score }
+            })
+            .collect();
 
-            if let Some(first_char) = item.text.chars().next() {
-                //let res = search.query(&(first_char as u8));
-                let mut buf = [0; 4];
-                let res = search.query_until(first_char.encode_utf8(&mut buf).as_bytes());
-
-
-                match res {
-                    Ok(answer) => {
-                        match answer {
-                            Answer::Prefix => {
-                                search_answer.push_str(&item.text);
-                                //info!("prefix: {}", item.text);
-                            }
-                            Answer::Match => {
-                                search_answer.push_str(&item.text);
-                                //info!("match: {}", item.text);
-                            }
-                            Answer::PrefixAndMatch => {
-                                search_answer.push_str(&item.text);
-                                //info!("p&m: {}", item.text);
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        // Handle the error case
-                        //info!("error: {}", item.text);
-                    }
-                }
+        suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        self.suggestions = suggestions;
 
-                // match res {
-                //     Some(Answer::Prefix) => {
-                //         info!("prefix: {}", item.text);
-                //     }
-                //     Some(Answer::Match) => {
-                //         info!("match: {}", item.text);
-                //     }
-                //     Some(Answer::PrefixAndMatch) => {
-                //         info!("p&m: {}", item.text);
-                //     }
-                //     _ => {
-                //         info!("no match: {}", item.text);
-                //     }
-                // }
+        let formatted: Vec<&str> = self.suggestions.iter().map(|item| item.text.as_str()).collect();
+        debug!("search result: {:?}", formatted);
+    }
+
+    /// Language model score for the word given the committed context, 0 when no model is loaded.
+    fn language_score(&self, word: &str) -> f32 {
+        self.log_prob(word).map_or(0.0, |log_prob| self.params.language_model_weight * log_prob)
+    }
+
+    /// log10 probability of the word following the context.
+    /// Mixes the language model with the users own history, None when neither knows anything.
+    fn log_prob(&self, word: &str) -> Option<f32> {
+        let model = self.language_model.as_ref().filter(|model| !model.is_empty());
+        let history = Some(&self.history).filter(|history| !history.is_empty());
+        match (model, history) {
+            (Some(model), Some(history)) => {
+                let probability = (1.0 - HISTORY_WEIGHT) * 10f32.powf(model.log_prob(&self.context, word))
+                    + HISTORY_WEIGHT * 10f32.powf(history.log_prob(&self.context, word));
+                Some(probability.log10())
             }
+            (Some(model), None) => Some(model.log_prob(&self.context, word)),
+            (None, Some(history)) => Some(history.log_prob(&self.context, word)),
+            (None, None) => None,
         }
-        info!("search result: {:?}", search_answer); //search.value();
-        search.reset();
     }
 
     pub fn load_dictionary(&mut self) {
@@ -283,16 +1002,43 @@ impl SearchHandler {
             return
         }
 
+        if self.user_dictionary.is_none() {
+            self.user_dictionary = Some(UserDictionary::load());
+        }
+
+        if self.languages.is_empty() {
+            self.languages = discover_languages();
+        }
+        let language = self.languages
+            .iter()
+            .find(|language| language.tag == self.language)
+            .cloned()
+            .unwrap_or_else(|| {
+                warn!("No dictionary for language \"{}\", using the builtin one", self.language);
+                Language::builtin()
+            });
+        // the language switcher, contractions and language model follow what is loaded
+        self.language = language.tag.clone();
+
         let now = std::time::Instant::now();
-        let mut builder = TrieBuilder::new();
-        for word in globals::DICTIONARY.split_whitespace() {
-            builder.push(word);
+        let mut trie = WordTrie::new();
+        for word in language.words().split_whitespace() {
+            trie.insert(word);
         }
 
-        let trie = builder.build();
-        info!("Dictionary loaded in {}ms", now.elapsed().as_millis());
+        // words with apostrophes, which are typed without them
+        language.contractions().apply(&mut trie);
+
+        // merge in the words the user has taught us
+        for item in self.user_dictionary.iter().flat_map(|user_dictionary| user_dictionary.learned()) {
+            trie.insert(&item.word);
+        }
+
+        info!("Dictionary \"{}\" loaded in {}ms, {} words", language.tag, now.elapsed().as_millis(), trie.len());
         self.dictionary = Some(trie);
 
+        self.load_language_model();
+
         //let cracklib = include_str!("/usr/share/cracklib/cracklib-small").split_whitespace();
         // let (mut hits, mut misses) = (0,0);
         // for word in cracklib {
@@ -301,4 +1047,38 @@ impl SearchHandler {
         // info!("Hits {}, Misses {}", hits, misses);
     }
 
-}
\ No newline at end of file
+    /// Rebuilds the dictionary, eg. after the learned words change.
+    fn reload_dictionary(&mut self) {
+        self.dictionary = None;
+        self.load_dictionary();
+    }
+
+    /// Loads the n-gram language model for the active language, if one has been built.
+    pub fn load_language_model(&mut self) {
+        if self.language_model.is_some() {
+            return
+        }
+
+        let path = self.languages
+            .iter()
+            .find(|language| language.tag == self.language)
+            .and_then(|language| language.language_model_path());
+        let Some(path) = path else {
+            info!("No language model found, ranking by key path only");
+            self.language_model = Some(NgramModel::default());
+            return
+        };
+
+        let now = std::time::Instant::now();
+        match NgramModel::load(&path) {
+            Ok(model) => {
+                info!("Language model loaded in {}ms, {} words", now.elapsed().as_millis(), model.vocab_len());
+                self.language_model = Some(model);
+            }
+            Err(err) => {
+                error!("Failed to load language model \"{}\", with error: {}", path.display(), err);
+                self.language_model = Some(NgramModel::default());
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use iced::Point;
+use std::f32::consts::FRAC_PI_4;
+use std::io;
+use std::path::{
+    Path,
+    PathBuf
+};
+
+pub static SHAPE_FILE: &str = "shapes.tsv"; // in the xdg data home
+static SAMPLE_POINTS: usize = 64; // points each stroke is resampled to
+static SQUARE_SIZE: f32 = 250.0; // strokes are scaled to fit this square
+static ANGLE_RANGE: f32 = FRAC_PI_4; // radians either side of the indicative angle to search for the best fit
+static ANGLE_PRECISION: f32 = 0.035; // radians, about 2 degrees
+static MIN_SCORE: f32 = 0.8; // 0-1, strokes that fit no shape this well are not recognised
+static GOLDEN_RATIO: f32 = 0.618_034; // for the golden section search
+
+/// A named stroke, normalised so it can be compared to any other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub name: String,
+    points: Vec<Point>,
+}
+
+/// Recognises shapes drawn on the keyboard, eg. a circle or a check mark, using the $1 unistroke recogniser.
+/// A stroke is resampled to evenly spaced points, rotated so its first point is at angle 0 from the centroid,
+/// scaled to a square and centered, then compared to each recorded shape at the rotation that fits best.
+/// A name can have several recordings, to allow for the ways it is drawn.
+/// Stored in $XDG_DATA_HOME/surfboard/shapes.tsv as tab separated lines of name and `x,y` points.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeRecognizer {
+    path: Option<PathBuf>,
+    shapes: Vec<Shape>,
+}
+
+impl ShapeRecognizer {
+    /// Loads the recorded shapes, empty if none have been recorded yet.
+    pub fn load() -> Self {
+        let path = match xdg::BaseDirectories::with_prefix("surfboard").map(|dirs| dirs.place_data_file(SHAPE_FILE)) {
+            Ok(Ok(path)) => path,
+            _ => {
+                error!("Unable to find the xdg data dir, shapes will not be saved");
+                return ShapeRecognizer::default()
+            }
+        };
+
+        if !path.exists() {
+            return ShapeRecognizer {
+                path: Some(path),
+                shapes: Vec::new(),
+            }
+        }
+
+        match Self::load_from(&path) {
+            Ok(recognizer) => recognizer,
+            Err(err) => {
+                error!("Failed to load shapes \"{}\", with error: {}", path.display(), err);
+                ShapeRecognizer {
+                    path: Some(path),
+                    shapes: Vec::new(),
+                }
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut shapes = Vec::new();
+        for line in contents.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue
+            }
+            let Some((name, points)) = line.split_once('\t') else {
+                warn!("Skipping malformed shape line: {:?}", line);
+                continue
+            };
+            let points: Option<Vec<Point>> = points
+                .split_whitespace()
+                .map(|point| {
+                    let (x, y) = point.split_once(',')?;
+                    Some(Point::new(x.parse().ok()?, y.parse().ok()?))
+                })
+                .collect();
+            match points {
+                Some(points) if points.len() == SAMPLE_POINTS => shapes.push(Shape {
+                    name: name.to_string(),
+                    points,
+                }),
+                _ => warn!("Skipping malformed shape line: {:?}", line),
+            }
+        }
+
+        Ok(ShapeRecognizer {
+            path: Some(path.to_path_buf()),
+            shapes,
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(())
+        };
+        let mut contents = String::from("# name\tnormalised points\n");
+        for shape in self.shapes.iter() {
+            let points: Vec<String> = shape.points.iter().map(|point| format!("{},{}", point.x, point.y)).collect();
+            contents.push_str(&format!("{}\t{}\n", shape.name, points.join(" ")));
+        }
+        std::fs::write(path, contents)
+    }
+
+    /// Names of the recorded shapes, without repeats.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.shapes.iter().map(|shape| shape.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Records another way of drawing the named shape, returns false if the stroke is too small to use.
+    pub fn add(&mut self, name: &str, points: &[Point]) -> bool {
+        let Some(points) = normalize(points) else {
+            return false
+        };
+        self.shapes.push(Shape {
+            name: name.to_string(),
+            points,
+        });
+        true
+    }
+
+    /// Removes every recording of the named shape.
+    pub fn remove(&mut self, name: &str) {
+        self.shapes.retain(|shape| shape.name != name);
+    }
+
+    /// The shape the stroke fits best, with how well it fits, 0-1, None if it fits none well enough.
+    pub fn recognize(&self, points: &[Point]) -> Option<(String, f32)> {
+        let points = normalize(points)?;
+        let half_diagonal = 0.5 * (2.0 * SQUARE_SIZE * SQUARE_SIZE).sqrt();
+        self.shapes
+            .iter()
+            .map(|shape| {
+                let distance = distance_at_best_angle(&points, &shape.points);
+                (shape.name.clone(), 1.0 - distance / half_diagonal)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, score)| *score >= MIN_SCORE)
+    }
+}
+
+
+/// Resamples, rotates, scales and centers the stroke, None if it is a dot.
+fn normalize(points: &[Point]) -> Option<Vec<Point>> {
+    if points.len() < 2 || path_length(points) <= 0.0 {
+        return None
+    }
+    let points = resample(points, SAMPLE_POINTS);
+    let center = centroid(&points);
+    let angle = (points[0].y - center.y).atan2(points[0].x - center.x);
+    let points = rotate_by(&points, -angle);
+    let points = scale_to_square(&points);
+    let center = centroid(&points);
+    Some(points.iter().map(|point| Point::new(point.x - center.x, point.y - center.y)).collect())
+}
+
+/// Evenly spaced points along the stroke.
+fn resample(points: &[Point], count: usize) -> Vec<Point> {
+    let interval = path_length(points) / (count - 1) as f32;
+    let mut resampled = vec![points[0]];
+    let mut covered = 0.0;
+    let mut previous = points[0];
+    let mut index = 1;
+    while index < points.len() {
+        let point = points[index];
+        let distance = previous.distance(point);
+        if covered + distance >= interval && distance > 0.0 {
+            let t = (interval - covered) / distance;
+            let new_point = Point::new(previous.x + t * (point.x - previous.x), previous.y + t * (point.y - previous.y));
+            resampled.push(new_point);
+            previous = new_point; // the rest of this segment is measured from the new point
+            covered = 0.0;
+        } else {
+            covered += distance;
+            previous = point;
+            index += 1;
+        }
+    }
+    // rounding can leave the last point off
+    while resampled.len() < count {
+        resampled.push(*points.last().unwrap());
+    }
+    resampled.truncate(count);
+    resampled
+}
+
+fn path_length(points: &[Point]) -> f32 {
+    points.windows(2).map(|pair| pair[0].distance(pair[1])).sum()
+}
+
+fn centroid(points: &[Point]) -> Point {
+    let count = points.len() as f32;
+    Point::new(
+        points.iter().map(|point| point.x).sum::<f32>() / count,
+        points.iter().map(|point| point.y).sum::<f32>() / count,
+    )
+}
+
+fn rotate_by(points: &[Point], angle: f32) -> Vec<Point> {
+    let center = centroid(points);
+    let (sin, cos) = angle.sin_cos();
+    points
+        .iter()
+        .map(|point| {
+            let (x, y) = (point.x - center.x, point.y - center.y);
+            Point::new(x * cos - y * sin + center.x, x * sin + y * cos + center.y)
+        })
+        .collect()
+}
+
+/// Scales each axis to the square, so a shape matches however wide or tall it was drawn.
+/// Straight strokes stay thin rather than being stretched across the square.
+fn scale_to_square(points: &[Point]) -> Vec<Point> {
+    let (min_x, max_x) = points.iter().fold((f32::MAX, f32::MIN), |(min, max), point| (min.min(point.x), max.max(point.x)));
+    let (min_y, max_y) = points.iter().fold((f32::MAX, f32::MIN), |(min, max), point| (min.min(point.y), max.max(point.y)));
+    let size = (max_x - min_x).max(max_y - min_y);
+    let width = (max_x - min_x).max(size * 0.1);
+    let height = (max_y - min_y).max(size * 0.1);
+    points
+        .iter()
+        .map(|point| Point::new(point.x * SQUARE_SIZE / width, point.y * SQUARE_SIZE / height))
+        .collect()
+}
+
+/// Average distance between the strokes at the rotation that fits best, found with a golden section search.
+fn distance_at_best_angle(points: &[Point], shape: &[Point]) -> f32 {
+    let (mut from, mut to) = (-ANGLE_RANGE, ANGLE_RANGE);
+    let mut x1 = GOLDEN_RATIO * from + (1.0 - GOLDEN_RATIO) * to;
+    let mut f1 = path_distance(&rotate_by(points, x1), shape);
+    let mut x2 = (1.0 - GOLDEN_RATIO) * from + GOLDEN_RATIO * to;
+    let mut f2 = path_distance(&rotate_by(points, x2), shape);
+    while (to - from).abs() > ANGLE_PRECISION {
+        if f1 < f2 {
+            to = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = GOLDEN_RATIO * from + (1.0 - GOLDEN_RATIO) * to;
+            f1 = path_distance(&rotate_by(points, x1), shape);
+        } else {
+            from = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = (1.0 - GOLDEN_RATIO) * from + GOLDEN_RATIO * to;
+            f2 = path_distance(&rotate_by(points, x2), shape);
+        }
+    }
+    f1.min(f2)
+}
+
+/// Average distance between the matching points of two strokes.
+fn path_distance(a: &[Point], b: &[Point]) -> f32 {
+    let total: f32 = a.iter().zip(b.iter()).map(|(a, b)| a.distance(*b)).sum();
+    total / a.len().min(b.len()).max(1) as f32
+}
@@ -0,0 +1,240 @@
+use iced::Point;
+use crate::lang::*;
+use crate::utils::*;
+use super::*;
+
+static MIN_LIKELIHOOD: f32 = 0.001; // so far away keys cost a lot, but not infinitely
+static EDIT_COST: f32 = 3.0; // a letter that wasn't tapped, or a tap that isn't in the word
+static MAX_COST: f32 = 7.0; // words that cost more than this are not suggested
+
+/// A tapped point, with how likely it was meant for each letter key.
+#[derive(Debug, Clone)]
+pub struct Tap {
+    pub point: Option<Point>, // None for a handwritten letter
+    pub key: String, // label of the key the tap was most likely meant for
+    pub likelihoods: Vec<(String, f32)>, // folded letters of each letter key, and 0-1 for the key
+}
+
+impl Tap {
+    /// Cost of reading the tap as the letters of a key that end the path with c, from the edit distance before them.
+    /// 0 on the key center and rising with the distance. A key's letters can be several chars, eg. with marks
+    /// or a conjunct, and the tap takes all of them, any other char costs as much as a far away key.
+    fn cost(&self, steps: &[Step], step: usize, c: char, index: usize) -> f32 {
+        let miss = steps[step].row[index] - MIN_LIKELIHOOD.ln();
+        self.likelihoods
+            .iter()
+            .filter_map(|(letters, likelihood)| {
+                let row = row_before(steps, step, c, letters)?;
+                Some(row[index] - likelihood.max(MIN_LIKELIHOOD).ln())
+            })
+            .fold(miss, f32::min)
+    }
+}
+
+/// A char followed in the dictionary, with the edit distance row after it.
+/// Steps link back to the one before, so a tap can match the last few chars at once.
+#[derive(Debug, Clone)]
+struct Step {
+    parent: Option<usize>, // None at the start of a word, including after an elision
+    c: Option<char>,
+    row: Vec<f32>,
+}
+
+/// The row before the letters, if the path through the step then c ends with them.
+fn row_before<'a>(steps: &'a [Step], step: usize, c: char, letters: &str) -> Option<&'a [f32]> {
+    let mut chars = letters.chars().rev();
+    if chars.next() != Some(c) {
+        return None
+    }
+    let mut step = step;
+    for letter in chars {
+        if steps[step].c != Some(letter) {
+            return None
+        }
+        step = steps[step].parent?;
+    }
+    Some(&steps[step].row)
+}
+
+/// Decodes tapped letters, allowing for taps that land on a neighbouring key.
+/// Each tap scores every letter key by its distance from where the user aims for the key,
+/// and dictionary words are matched against the taps with an edit distance
+/// where swapping a letter costs less the closer its key was to the tap.
+#[derive(Debug, Clone, Default)]
+pub struct TapDecoder {
+    taps: Vec<Tap>,
+}
+
+impl TapDecoder {
+    pub fn reset(&mut self) {
+        self.taps.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.taps.is_empty()
+    }
+
+    /// Removes the last tap, None if there were none.
+    pub fn pop(&mut self) -> Option<Tap> {
+        self.taps.pop()
+    }
+
+    /// Number of chars typed for the taps, so they can be deleted when the word is corrected.
+    /// A key can type several chars, eg. a conjunct.
+    pub fn len(&self) -> usize {
+        self.taps.iter().map(|tap| tap.key.chars().count()).sum()
+    }
+
+    /// The text typed by a key that ends a word, None for keys that don't.
+    pub fn separator(label: &str) -> Option<String> {
+        match label.trim() {
+            "" => Some(String::from(" ")), // the space bar
+            "Enter" => Some(String::from("\n")),
+            "." | "," | "!" | "?" | ":" | ";" => Some(label.trim().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Adds a tap on the key with the label, returns the letter to type.
+    /// Keys that aren't a single letter are not part of a word, so return None.
+    /// The letter is the one the user most likely aimed for, which near the edge of a key
+    /// can be its neighbour, if that is where the user is known to tap.
+    pub fn push(&mut self, point: Point, label: &str, keys: &[Candidate], targets: &KeyTargets) -> Option<String> {
+        unicode::key_letters(label)?;
+
+        // keys report their bounds on every layout, the latest for each label is last
+        let mut labels: Vec<&str> = Vec::new();
+        let mut likelihoods: Vec<(String, f32)> = Vec::new();
+        for candidate in keys.iter().rev() {
+            let Some(letters) = unicode::key_letters(&candidate.text) else {
+                continue
+            };
+            if labels.contains(&candidate.text.as_str()) {
+                continue
+            }
+            labels.push(&candidate.text);
+            let center = targets.center(&candidate.text, candidate.bounds);
+            let sigma = targets.sigma(&candidate.text, candidate.bounds);
+            likelihoods.push((letters, functions::gaussian_pdf(point, center, sigma)));
+        }
+
+        let index = (0..likelihoods.len()).max_by(|a, b| likelihoods[*a].1.total_cmp(&likelihoods[*b].1))?;
+        let key = labels[index].to_string();
+        self.taps.push(Tap { point: Some(point), key: key.clone(), likelihoods });
+        Some(key)
+    }
+
+    /// Adds a handwritten letter, with how likely the strokes were each letter, returns false if it isn't a letter.
+    /// It is read like a tap, so letters that were written unclearly are corrected by the dictionary.
+    pub fn push_written(&mut self, letter: &str, candidates: &[(String, f32)]) -> bool {
+        if unicode::key_letters(letter).is_none() {
+            return false
+        }
+        let likelihoods = candidates
+            .iter()
+            .filter_map(|(candidate, likelihood)| Some((unicode::key_letters(candidate)?, *likelihood)))
+            .collect();
+        self.taps.push(Tap { point: None, key: letter.to_string(), likelihoods });
+        true
+    }
+
+    /// The taps of the current word, in the order they were made.
+    pub fn taps(&self) -> &[Tap] {
+        &self.taps
+    }
+
+    /// The letters exactly as they were tapped.
+    pub fn literal(&self) -> String {
+        self.taps.iter().map(|tap| tap.key.as_str()).collect()
+    }
+
+    /// Dictionary words close to the taps, with how well they fit them, higher is better.
+    /// The dictionary is followed by folded letters, so accents on the words are free.
+    /// Walks the dictionary one letter at a time, keeping a row of the edit distance
+    /// to each number of taps, and stops following letters once every entry is too costly.
+    /// A tap on a key with several folded letters matches all of them in one swap.
+    pub fn words(&self, trie: &WordTrie) -> Vec<(String, f32)> {
+        let length = self.taps.len();
+        let mut words = Vec::new();
+        if length == 0 {
+            return words
+        }
+
+        let row: Vec<f32> = (0..=length).map(|index| index as f32 * EDIT_COST).collect();
+        let mut steps = vec![Step { parent: None, c: None, row }];
+        let mut stack = vec![(WordTrie::ROOT, 0, None)];
+        while let Some((node, step, elision)) = stack.pop() {
+            let cost = steps[step].row[length];
+            if cost <= MAX_COST {
+                words.extend(trie.words_after(node, elision).map(|word| (word, -cost)));
+            }
+
+            // a word can start again after an elision, eg. "l'" in "l'eau"
+            if elision.is_none() {
+                for elision in trie.elisions_at(node) {
+                    steps.push(Step { parent: None, c: None, row: steps[step].row.clone() });
+                    stack.push((WordTrie::ROOT, steps.len() - 1, Some(elision)));
+                }
+            }
+
+            for (c, child) in trie.children(node) {
+                let row = &steps[step].row;
+                let mut next = vec![row[0] + EDIT_COST];
+                for index in 1..=length {
+                    let swap = self.taps[index - 1].cost(&steps, step, c, index - 1);
+                    let insert = row[index] + EDIT_COST;
+                    let delete = next[index - 1] + EDIT_COST;
+                    next.push(swap.min(insert).min(delete));
+                }
+                if next.iter().any(|cost| *cost <= MAX_COST) {
+                    steps.push(Step { parent: Some(step), c: Some(c), row: next });
+                    stack.push((child, steps.len() - 1, elision));
+                }
+            }
+        }
+        words
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tap right on the key with the label.
+    fn tap(label: &str) -> Tap {
+        Tap {
+            point: None,
+            key: label.to_string(),
+            likelihoods: vec![(unicode::key_letters(label).unwrap(), 1.0)],
+        }
+    }
+
+    fn best(taps: &[&str], words: &[&str]) -> Option<(String, f32)> {
+        let mut trie = WordTrie::new();
+        for word in words {
+            trie.insert(word);
+        }
+        let decoder = TapDecoder { taps: taps.iter().copied().map(tap).collect() };
+        decoder.words(&trie).into_iter().max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    #[test]
+    fn taps_spell_the_word() {
+        assert_eq!(best(&["c", "a", "t"], &["cat", "car"]), Some((String::from("cat"), 0.0)));
+    }
+
+    #[test]
+    fn a_tap_on_a_key_with_several_letters_spells_all_of_them() {
+        // "ß" folds to "ss", "þ" to "th"
+        assert_eq!(best(&["s", "t", "r", "a", "ß", "e"], &["straße", "strata"]), Some((String::from("straße"), 0.0)));
+        assert_eq!(best(&["þ", "i", "n", "g"], &["þing", "ping"]), Some((String::from("þing"), 0.0)));
+    }
+
+    #[test]
+    fn a_key_with_several_letters_does_not_match_one_of_them() {
+        let (word, score) = best(&["ß"], &["s"]).unwrap();
+        assert_eq!(word, "s");
+        assert!(score < 0.0);
+    }
+}
@@ -0,0 +1,157 @@
+use iced::{
+    Point,
+    Rectangle,
+    Size,
+    Vector
+};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{
+    Path,
+    PathBuf
+};
+
+pub static TARGET_DIR: &str = "targets"; // in the xdg data home
+pub static TAP_SIGMA: f32 = 0.5; // spread of a tap around the key center, as a share of the key width
+static MIN_SIGMA: f32 = 0.2; // learned spreads are kept above this share of the key width
+static PRIOR_COUNT: f32 = 10.0; // taps needed before the learned target counts as much as the key center
+
+/// Where the user taps a key, learned from their taps.
+/// Kept as a running mean of the offset from the key center, and the sum of squared distances from the mean.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyTarget {
+    pub count: u32,
+    pub offset: Vector, // pixels from the key center
+    pub squared_distance: f32, // sum of squared pixels from the mean offset
+}
+
+impl KeyTarget {
+    /// Adds a tap, using Welford's method so the mean and spread stay accurate.
+    pub fn learn(&mut self, offset: Vector) {
+        self.count += 1;
+        let before = offset - self.offset;
+        self.offset = self.offset + before * (1.0 / self.count as f32);
+        let after = offset - self.offset;
+        self.squared_distance += before.x * after.x + before.y * after.y;
+    }
+
+    /// Spread of the taps along each axis, in pixels.
+    pub fn sigma(&self) -> f32 {
+        match self.count {
+            0 => 0.0,
+            count => (self.squared_distance / (2.0 * count as f32)).sqrt(),
+        }
+    }
+
+    /// How much the learned target is trusted over the key center, 0-1.
+    fn trust(&self) -> f32 {
+        self.count as f32 / (self.count as f32 + PRIOR_COUNT)
+    }
+}
+
+/// Personal key targets for one layout at one window size, as users tend to tap off center,
+/// especially at the edges of the keyboard.
+/// Stored under $XDG_DATA_HOME/surfboard/targets as tab separated lines of:
+/// key, count, offset x, offset y, squared distance.
+#[derive(Debug, Clone, Default)]
+pub struct KeyTargets {
+    path: Option<PathBuf>,
+    targets: BTreeMap<String, KeyTarget>,
+}
+
+impl KeyTargets {
+    /// Loads the targets for the layout and window size, empty if none have been learned yet.
+    pub fn load(layout: &str, size: Size) -> Self {
+        let name = format!("{TARGET_DIR}/{layout}-{}x{}.tsv", size.width as u32, size.height as u32);
+        let path = match xdg::BaseDirectories::with_prefix("surfboard").map(|dirs| dirs.place_data_file(&name)) {
+            Ok(Ok(path)) => path,
+            _ => {
+                error!("Unable to find the xdg data dir, key targets will not be saved");
+                return KeyTargets::default()
+            }
+        };
+
+        if !path.exists() {
+            return KeyTargets {
+                path: Some(path),
+                targets: BTreeMap::new(),
+            }
+        }
+
+        match Self::load_from(&path) {
+            Ok(targets) => targets,
+            Err(err) => {
+                error!("Failed to load key targets \"{}\", with error: {}", path.display(), err);
+                KeyTargets {
+                    path: Some(path),
+                    targets: BTreeMap::new(),
+                }
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut targets = BTreeMap::new();
+        for line in contents.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [key, count, x, y, squared_distance] = fields[..] else {
+                warn!("Skipping malformed key target line: {:?}", line);
+                continue
+            };
+            let (Ok(count), Ok(x), Ok(y), Ok(squared_distance)) = (count.parse(), x.parse(), y.parse(), squared_distance.parse()) else {
+                warn!("Skipping malformed key target line: {:?}", line);
+                continue
+            };
+            targets.insert(key.to_string(), KeyTarget {
+                count,
+                offset: Vector::new(x, y),
+                squared_distance,
+            });
+        }
+
+        Ok(KeyTargets {
+            path: Some(path.to_path_buf()),
+            targets,
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(())
+        };
+        let mut contents = String::from("# key\tcount\toffset x\toffset y\tsquared distance\n");
+        for (key, target) in self.targets.iter() {
+            contents.push_str(&format!("{}\t{}\t{}\t{}\t{}\n", key, target.count, target.offset.x, target.offset.y, target.squared_distance));
+        }
+        std::fs::write(path, contents)
+    }
+
+    /// Adds a tap meant for the key, at the offset from its center.
+    pub fn learn(&mut self, key: &str, offset: Vector) {
+        self.targets.entry(key.to_string()).or_default().learn(offset);
+    }
+
+    /// Where the user aims for the key, moving from the key center toward the learned target as taps are learned.
+    pub fn center(&self, key: &str, bounds: Rectangle) -> Point {
+        match self.targets.get(key) {
+            Some(target) => bounds.center() + target.offset * target.trust(),
+            None => bounds.center(),
+        }
+    }
+
+    /// How far taps for the key spread around its center, in pixels.
+    pub fn sigma(&self, key: &str, bounds: Rectangle) -> f32 {
+        let prior = bounds.width * TAP_SIGMA;
+        match self.targets.get(key) {
+            Some(target) => {
+                let learned = target.sigma().max(bounds.width * MIN_SIGMA);
+                prior + (learned - prior) * target.trust()
+            }
+            None => prior,
+        }
+    }
+}
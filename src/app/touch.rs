@@ -0,0 +1,134 @@
+use iced::Point;
+use std::collections::BTreeMap;
+
+static MOVE_THRESHOLD: f32 = 20.0; // pixels the fingers move together before it is a drag
+static PINCH_THRESHOLD: f32 = 0.15; // change in the spread of the fingers, as a share, before it is a pinch
+static SWIPE_DISTANCE: f32 = 60.0; // pixels three fingers move sideways to switch views
+
+/// What the fingers are doing, decided once they have moved far enough.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TouchMode {
+    Move, // two fingers dragging the window
+    Pinch, // two fingers scaling the height
+    Swipe, // three fingers switching views
+}
+
+/// A multi-finger gesture, as the fingers move and lift.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchGesture {
+    Move { start: bool, centroid: Point },
+    Pinch { start: bool, scale: f32 }, // spread of the fingers over their spread when the pinch began
+    EndMove,
+    EndPinch,
+    Swipe(i32), // views to step, 1 for the next, -1 for the previous
+}
+
+/// Tracks the fingers on the screen, to read drags, pinches and swipes made with more than one.
+/// Once a second finger lands every finger is part of the multi-finger gesture until they are all lifted,
+/// so the fingers left as they lift one at a time don't start a glide.
+#[derive(Debug, Clone, Default)]
+pub struct MultiTouch {
+    fingers: BTreeMap<u64, (Point, Point)>, // id -> position when the gesture began, current position
+    max_fingers: usize, // most fingers down at once since the first landed
+    mode: Option<TouchMode>,
+    swipe: Option<(Point, Point)>, // centroid of three fingers when they landed, and the latest
+}
+
+impl MultiTouch {
+    /// Adds a finger, returns the end of a drag or pinch that a third finger turns into a swipe.
+    pub fn press(&mut self, id: u64, position: Point) -> Option<TouchGesture> {
+        self.fingers.insert(id, (position, position));
+        self.max_fingers = self.max_fingers.max(self.fingers.len());
+
+        // the gesture starts again from where the fingers are now
+        for (start, current) in self.fingers.values_mut() {
+            *start = *current;
+        }
+        if self.fingers.len() < 3 {
+            return None
+        }
+        let centroid = self.centroid(|(_, current)| *current);
+        self.swipe = Some((centroid, centroid));
+        match self.mode.replace(TouchMode::Swipe) {
+            Some(TouchMode::Move) => Some(TouchGesture::EndMove),
+            Some(TouchMode::Pinch) => Some(TouchGesture::EndPinch),
+            _ => None,
+        }
+    }
+
+    /// True from when a second finger lands until every finger has lifted.
+    pub fn is_multi(&self) -> bool {
+        self.max_fingers >= 2
+    }
+
+    pub fn move_to(&mut self, id: u64, position: Point) -> Option<TouchGesture> {
+        self.fingers.get_mut(&id)?.1 = position;
+        if self.mode == Some(TouchMode::Swipe) {
+            // the centroid jumps once a finger has lifted, so only follow it while all three are down
+            if self.fingers.len() >= 3 {
+                let centroid = self.centroid(|(_, current)| *current);
+                if let Some((_, latest)) = self.swipe.as_mut() {
+                    *latest = centroid;
+                }
+            }
+            return None
+        }
+        if self.fingers.len() != 2 || self.max_fingers != 2 {
+            return None
+        }
+
+        let start_centroid = self.centroid(|(start, _)| *start);
+        let centroid = self.centroid(|(_, current)| *current);
+        let scale = self.spread(|(_, current)| *current) / self.spread(|(start, _)| *start).max(1.0);
+        match self.mode {
+            Some(TouchMode::Move) => Some(TouchGesture::Move { start: false, centroid }),
+            Some(TouchMode::Pinch) => Some(TouchGesture::Pinch { start: false, scale }),
+            _ if (scale - 1.0).abs() > PINCH_THRESHOLD => {
+                self.mode = Some(TouchMode::Pinch);
+                Some(TouchGesture::Pinch { start: true, scale })
+            }
+            _ if start_centroid.distance(centroid) > MOVE_THRESHOLD => {
+                self.mode = Some(TouchMode::Move);
+                Some(TouchGesture::Move { start: true, centroid })
+            }
+            _ => None,
+        }
+    }
+
+    /// Lifts a finger, the gesture ends when the last one lifts.
+    pub fn lift(&mut self, id: u64) -> Option<TouchGesture> {
+        self.fingers.remove(&id);
+        if !self.fingers.is_empty() {
+            return None
+        }
+
+        let mode = self.mode.take();
+        let swipe = self.swipe.take();
+        self.max_fingers = 0;
+        match mode? {
+            TouchMode::Move => Some(TouchGesture::EndMove),
+            TouchMode::Pinch => Some(TouchGesture::EndPinch),
+            TouchMode::Swipe => {
+                let (start, end) = swipe?;
+                let (dx, dy) = (end.x - start.x, end.y - start.y);
+                match dx.abs() > SWIPE_DISTANCE && dx.abs() > dy.abs() {
+                    true => Some(TouchGesture::Swipe(match dx > 0.0 { true => 1, false => -1 })),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    fn centroid(&self, position: impl Fn(&(Point, Point)) -> Point) -> Point {
+        let count = self.fingers.len().max(1) as f32;
+        let (x, y) = self.fingers.values().map(position).fold((0.0, 0.0), |(x, y), point| (x + point.x, y + point.y));
+        Point::new(x / count, y / count)
+    }
+
+    /// Average distance of the fingers from their centroid.
+    fn spread(&self, position: impl Fn(&(Point, Point)) -> Point) -> f32 {
+        let centroid = self.centroid(&position);
+        let count = self.fingers.len().max(1) as f32;
+        self.fingers.values().map(|finger| position(finger).distance(centroid)).sum::<f32>() / count
+    }
+}
@@ -4,7 +4,11 @@ use iced::{
 };
 use std::fmt;
 use super::*;
-use crate::{utils::*, views::*};
+use crate::{comp::Latch, lang::*, utils::*, views::*};
+use crate::utils::config::{
+    GestureAction,
+    GestureActions
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum View {
@@ -13,6 +17,7 @@ pub enum View {
     Launcher,
     QuickPick,
     Pick,
+    Handwriting,
     // Add more views/layouts here
 }
 
@@ -24,20 +29,39 @@ impl std::fmt::Display for View {
             View::Launcher => write!(f, "Launcher"),
             View::QuickPick => write!(f, "Quick Pick"),
             View::Pick => write!(f, "Pick"),
+            View::Handwriting => write!(f, "Handwriting"),
             // Add more views/layouts here
         }
     }
 }
 
 impl View {
-    pub const ALL: [View; 5] = [
+    pub const ALL: [View; 6] = [
         View::CompactQwerty,
         View::Settings,
         View::Launcher,
         View::QuickPick,
         View::Pick,
+        View::Handwriting,
         // Add more views/layouts here
     ];
+
+    /// Stable name used in the config file.
+    pub fn id(&self) -> &'static str {
+        match self {
+            View::CompactQwerty => "compact_qwerty",
+            View::Settings => "settings",
+            View::Launcher => "launcher",
+            View::QuickPick => "quick_pick",
+            View::Pick => "pick",
+            View::Handwriting => "handwriting",
+            // Add more views/layouts here
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<View> {
+        View::ALL.into_iter().find(|view| view.id() == id)
+    }
 }
 
 
@@ -72,30 +96,59 @@ impl fmt::Debug for dyn ViewTrait + 'static {
 #[derive(Debug)]
 pub struct ViewHandler {
     pub current_view: View,
-    pub views: [Box<dyn ViewTrait>; 5], // Add more views/layouts here
+    pub views: [Box<dyn ViewTrait>; 6], // Add more views/layouts here
+    pub learned_words: Vec<LearnedWord>, // from the search handler, for review in the settings
+    pub languages: Vec<String>, // installed language tags, from the search handler
+    pub language: String, // active language tag
+    pub recording: bool, // glides are being recorded
+    pub shapes: Vec<String>, // names of the recorded shapes, from the gesture handler
+    pub shape_name: String, // name typed in the settings for the next shape to record
+    pub recording_shape: bool, // waiting for a shape to be drawn
+    pub shift: Latch, // from the output handler
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ChangeView(View),
-    ActionGesture(ActionDirection),
+    ActionGesture(ActionDirection, Option<String>), // direction, and the key the gesture started on
+    SetGestureAction(String, GestureAction), // config key, see `GestureActions`
+    StepView(i32), // switch to the next typing view, or a previous one if negative
+    ShapeGesture(String), // name of a recognised shape
+    Shapes(Vec<String>), // names of the recorded shapes
+    ShapeName(String),
+    RecordShape, // record the next gesture as a shape named shape_name
     ViewMessage(usize),
+    LearnedWords(Vec<LearnedWord>),
+    Languages(Vec<String>, String), // installed language tags, active language tag
+    LayoutLanguage(View, String), // set the default language of a layout
+    Recording(bool),
+    Shift(Latch),
+    Theme(String), // id of the theme to use
 }
 
 impl ViewHandler {
     pub fn new() -> Self {
-        let views: [Box<dyn ViewTrait>; 5] = [
+        let views: [Box<dyn ViewTrait>; 6] = [
             Box::new(CompactQwertyView::new()),
             Box::new(SettingsView::new()),
             Box::new(LauncherView::new()),
             Box::new(MiniPickView::new()),
             Box::new(PickView::new()),
+            Box::new(HandwritingView::new()),
             // Add more views/layouts here
         ];
 
         ViewHandler {
             current_view: View::CompactQwerty,
             views,
+            learned_words: Vec::new(),
+            languages: Vec::new(),
+            language: String::new(),
+            recording: false,
+            shapes: Vec::new(),
+            shape_name: String::new(),
+            recording_shape: false,
+            shift: Latch::Off,
         }
     }
 
@@ -115,24 +168,102 @@ impl ViewHandler {
     pub fn update(&mut self, message: Message) -> Task<main_app::Message> {
         match message {
             Message::ChangeView(view) => {
+                // the keys of the old view are gone, so they must not be glided or tapped on
+                let mut tasks = Vec::new();
+                if view != self.current_view {
+                    tasks.push(Task::done(search::Message::Reset).map(main_app::Message::SearchHandler));
+                    tasks.push(Task::done(search::Message::SetLayout(view.id().to_string())).map(main_app::Message::SearchHandler));
+                    tasks.push(Task::done(gesture::Message::SetWriting(view == View::Handwriting)).map(main_app::Message::GestureHandler));
+                }
                 self.current_view = view;
+
+                // layouts can have a default language
+                if let Some(tag) = config::get().layout_languages.get(view.id()) {
+                    tasks.push(Task::done(search::Message::SetLanguage(tag.clone())).map(main_app::Message::SearchHandler));
+                }
+                Task::batch(tasks)
+            }
+            Message::ActionGesture(direction, region) => {
+                let action = config::get().gestures.get(direction.id(), region.as_deref());
+                info!("Action gesture: {} {:?} -> {}", direction, region, action);
+                self.run_gesture_action(action)
+            }
+            Message::StepView(step) => {
+                let layouts: Vec<View> = self.views
+                    .iter()
+                    .filter(|view| view.has_gesture())
+                    .map(|view| view.class())
+                    .collect();
+                let index = layouts.iter().position(|view| *view == self.current_view).unwrap_or(0) as i32;
+                let view = layouts[(index + step).rem_euclid(layouts.len() as i32) as usize];
+                Task::done(Message::ChangeView(view)).map(main_app::Message::ViewHandler)
+            }
+            Message::ShapeGesture(name) => {
+                let action = config::get().gestures.get(&GestureActions::shape_key(&name), None);
+                info!("Shape gesture: {} -> {}", name, action);
+                self.run_gesture_action(action)
+            }
+            Message::SetGestureAction(key, action) => {
+                config::update(|config| config.gestures.set(key, action));
+                Task::none()
+            }
+            Message::Shapes(shapes) => {
+                // a new shape does nothing until it is given an action
+                config::update(|config| {
+                    for name in shapes.iter() {
+                        config.gestures.actions.entry(GestureActions::shape_key(name)).or_insert(GestureAction::None);
+                    }
+                });
+                self.shapes = shapes;
+                self.recording_shape = false;
+                Task::none()
+            }
+            Message::ShapeName(name) => {
+                self.shape_name = name;
                 Task::none()
             }
-            Message::ActionGesture(direction) => {
-                let view_class = match direction {
-                    ActionDirection::TopLeft => View::CompactQwerty,
-                    ActionDirection::Top => View::Settings,
-                    ActionDirection::TopRight => View::CompactQwerty,
-                    ActionDirection::Right => View::CompactQwerty,
-                    ActionDirection::BottomRight => View::CompactQwerty,
-                    ActionDirection::Bottom => View::Launcher,
-                    ActionDirection::BottomLeft => View::CompactQwerty,
-                    ActionDirection::Left => View::CompactQwerty,
-                    ActionDirection::LongPress => View::Pick,
-                };
-                Task::done(Message::ChangeView(view_class)).map(main_app::Message::ViewHandler)
+            Message::RecordShape => {
+                let name = self.shape_name.trim().to_string();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Task::none()
+                }
+                self.recording_shape = true;
+                self.shape_name.clear();
+                Task::done(gesture::Message::RecordShape(name)).map(main_app::Message::GestureHandler)
             }
             Message::ViewMessage(_) => self.current_view_mut().update(message),
+            Message::LearnedWords(mut words) => {
+                // most recently used first
+                words.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+                self.learned_words = words;
+                Task::none()
+            }
+            Message::Languages(languages, language) => {
+                self.languages = languages;
+                self.language = language;
+                Task::none()
+            }
+            Message::Recording(recording) => {
+                self.recording = recording;
+                Task::none()
+            }
+            Message::Theme(id) => {
+                theme::set(&id);
+                Task::none()
+            }
+            Message::Shift(shift) => {
+                self.shift = shift;
+                Task::none()
+            }
+            Message::LayoutLanguage(view, tag) => {
+                config::update(|config| {
+                    config.layout_languages.insert(view.id().to_string(), tag.clone());
+                });
+                match view == self.current_view {
+                    true => Task::done(search::Message::SetLanguage(tag)).map(main_app::Message::SearchHandler),
+                    false => Task::none(),
+                }
+            }
             //_ => Task::none()
         }
     }
@@ -141,6 +272,27 @@ impl ViewHandler {
         self.current_view().view(self)
     }
 
+    fn run_gesture_action(&mut self, action: GestureAction) -> Task<main_app::Message> {
+        match action {
+            GestureAction::None => Task::none(),
+            GestureAction::View(id) => match View::from_id(&id) {
+                Some(view) => Task::done(Message::ChangeView(view)).map(main_app::Message::ViewHandler),
+                None => {
+                    warn!("Gesture action has an unknown view: {}", id);
+                    Task::none()
+                }
+            },
+            GestureAction::Command(command) => {
+                if let Err(err) = std::process::Command::new("sh").arg("-c").arg(&command).spawn() {
+                    error!("Failed to run gesture command \"{}\", with error: {}", command, err);
+                }
+                Task::none()
+            }
+            GestureAction::Keys(chord) => Task::done(output::Message::Keys(chord)).map(main_app::Message::OutputHandler),
+            GestureAction::Hide => Task::done(window::Message::Hide).map(main_app::Message::WindowHandler),
+        }
+    }
+
     pub fn current_view(&self) -> &Box<dyn ViewTrait> {
         self.views.iter().find(|view| view.class() == self.current_view).expect("No matching view found")
     }
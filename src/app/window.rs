@@ -8,6 +8,9 @@ use iced::{
 
 use super::*;
 
+static MIN_HEIGHT: f32 = 150.0; // pixels the keyboard can be pinched down to
+static MAX_HEIGHT: f32 = 800.0;
+
 /// Handles all things window related.  
 #[derive(Clone, Debug)]
 pub struct WindowHandler {
@@ -17,12 +20,14 @@ pub struct WindowHandler {
     pub margin: (i32, i32, i32, i32), // top, right, bottom, left
     pub moving: bool,
     move_start: Option<Point>,
+    resize_start: Option<u32>, // height when the pinch began
 }
 
 
 #[derive(Debug, Clone)]
 pub enum Message {
     Dock(Dock),
+    Hide,
 }
 
 impl WindowHandler {
@@ -34,51 +39,59 @@ impl WindowHandler {
             margin: (0, 0, 0, 0),
             moving: false,
             move_start: None,
+            resize_start: None,
         }
     }
 
     pub fn update(&mut self, message: window::Message) -> Task<main_app::Message> {
-        
-        #[cfg(target_os="windows")]
         match message {
-            Message::Dock(dock) => {
-                if self.windowed {
-                    // todo need to chain commands as we need to restore the margins?
+            Message::Dock(dock) => self.dock(dock),
+            Message::Hide => {
+                info!("Hide keyboard");
+                iced::window::latest().and_then(|id| iced::window::minimize(id, true))
+            }
+        }
+    }
+
+    #[cfg_attr(not(target_os="windows"), allow(unused_variables))]
+    fn dock(&mut self, dock: Dock) -> Task<main_app::Message> {
+        #[cfg(target_os="windows")]
+        {
+            if self.windowed {
+                // todo need to chain commands as we need to restore the margins?
+                return Task::done(main_app::Message::AnchorSizeChange(
+                    Anchor::Left | Anchor::Top,
+                    self.size,
+                ))
+            }
+            self.dock = dock;
+            let size_dock = 400;
+            match dock {
+                Dock::Left => {
+                    return Task::done(main_app::Message::AnchorSizeChange(
+                        Anchor::Left | Anchor::Top | Anchor::Bottom,
+                        (size_dock, 0),
+                    ))
+                }
+                Dock::Right => {
                     return Task::done(main_app::Message::AnchorSizeChange(
-                        Anchor::Left | Anchor::Top,
-                        self.size,
+                        Anchor::Right | Anchor::Top | Anchor::Bottom,
+                        (size_dock, 0),
                     ))
                 }
-                self.dock = dock;
-                let size_dock = 400;
-                match dock {
-                    Dock::Left => {
-                        return Task::done(main_app::Message::AnchorSizeChange(
-                            Anchor::Left | Anchor::Top | Anchor::Bottom,
-                            (size_dock, 0),
-                        ))
-                    }
-                    Dock::Right => {
-                        return Task::done(main_app::Message::AnchorSizeChange(
-                            Anchor::Right | Anchor::Top | Anchor::Bottom,
-                            (size_dock, 0),
-                        ))
-                    }
-                    Dock::Bottom => {
-                        return Task::done(main_app::Message::AnchorSizeChange(
-                            Anchor::Bottom | Anchor::Left | Anchor::Right,
-                            (0, size_dock),
-                        ))
-                    }
-                    Dock::Top => {
-                        return Task::done(main_app::Message::AnchorSizeChange(
-                            Anchor::Top | Anchor::Left | Anchor::Right,
-                            (0, size_dock),
-                        ))
-                    }
+                Dock::Bottom => {
+                    return Task::done(main_app::Message::AnchorSizeChange(
+                        Anchor::Bottom | Anchor::Left | Anchor::Right,
+                        (0, size_dock),
+                    ))
+                }
+                Dock::Top => {
+                    return Task::done(main_app::Message::AnchorSizeChange(
+                        Anchor::Top | Anchor::Left | Anchor::Right,
+                        (0, size_dock),
+                    ))
                 }
             }
-            //_ => Task::none()
         }
 
         Task::none()
@@ -86,10 +99,16 @@ impl WindowHandler {
 
 
 
+    /// Starts moving the window. Without the layer shell the compositor moves it, as a window can't place itself.
     pub fn start_move(&mut self) -> Task<main_app::Message> {
         self.moving = true;
         self.move_start = None;
-        Task::none()
+
+        #[cfg(target_os="windows")]
+        return Task::none();
+
+        #[cfg(not(target_os="windows"))]
+        iced::window::latest().and_then(iced::window::drag)
     }
 
     pub fn end_move(&mut self) -> Task<main_app::Message> {
@@ -97,33 +116,60 @@ impl WindowHandler {
         Task::none()
     }
 
+    /// Moves the window so the point it was grabbed at stays under the pointer.
+    /// Positions are relative to the window, so the grab point is kept rather than the last position.
+    #[cfg_attr(not(target_os="windows"), allow(unused_variables))]
     pub fn update_move(&mut self, position: Point) -> Task<main_app::Message> {
-        // get windows initial position - the margin
-        if self.move_start.is_none() {
+        let Some(start) = self.move_start else {
             self.move_start = Some(position);
-            return Task::none();
-        }
+            return Task::none()
+        };
 
-        // calulate the difference
-        let diff = self.move_start.unwrap() - position;
-        info!("diff: {:?} {:?}", -diff.x as i32, -diff.y as i32);
+        #[cfg(target_os="windows")]
+        {
+            // the window is anchored top left, so its top and left margins place it
+            let diff = position - start;
+            self.margin.0 += diff.y as i32;
+            self.margin.3 += diff.x as i32;
+            return Task::done(main_app::Message::MarginChange(self.margin))
+        }
 
-        // calculate for the margin change
-        let y = -diff.y as i32 + self.margin.2;
-        let x = -diff.x as i32 + self.margin.3;
+        // the compositor is moving the window, see `start_move`
+        #[cfg(not(target_os="windows"))]
+        Task::none()
+    }
 
-        //info!("mar: {:?} {:?}", x as i32, y as i32);
+    pub fn start_resize(&mut self) -> Task<main_app::Message> {
+        self.resize_start = Some(self.size.1);
+        Task::none()
+    }
 
-        // store the mouse pos
-        self.move_start = Some(position);
-        
-        // apply margin to move window
-        self.margin.2 = y;
-        self.margin.3 = x;
-        info!("mar: {:?} {:?}", x as i32, y as i32);
-        //return Task::done(main_app::Message::MarginChange((y, 0, 0, x)))
+    pub fn end_resize(&mut self) -> Task<main_app::Message> {
+        self.resize_start = None;
         Task::none()
     }
+
+    /// Scales the height from where the pinch began.
+    pub fn update_resize(&mut self, scale: f32) -> Task<main_app::Message> {
+        let Some(start) = self.resize_start else {
+            return Task::none()
+        };
+        let height = (start as f32 * scale).clamp(MIN_HEIGHT, MAX_HEIGHT) as u32;
+        if height == self.size.1 {
+            return Task::none()
+        }
+        self.size.1 = height;
+        debug!("size: {:?}", self.size);
+
+        #[cfg(target_os="windows")]
+        return Task::done(main_app::Message::SizeChange(self.size));
+
+        #[cfg(not(target_os="windows"))]
+        {
+            let size = iced::Size::new(self.size.0 as f32, self.size.1 as f32);
+            iced::window::latest().and_then(move |id| iced::window::resize(id, size))
+        }
+    }
 }
 
 
@@ -157,4 +203,4 @@ impl std::fmt::Display for Dock {
             }
         )
     }
-}
\ No newline at end of file
+}
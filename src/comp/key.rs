@@ -11,12 +11,14 @@ use iced::{
     }, 
     border, 
     overlay, 
+    Border, 
     touch, 
     Color, 
     Element, 
     Event, 
     Length, 
     Padding, 
+    Point, 
     Rectangle, 
     Shadow, 
     Size, 
@@ -24,7 +26,30 @@ use iced::{
     Vector,
     widget::text
 };
-use iced_core::window;
+use iced_core::{
+    window,
+    Transformation
+};
+use std::time::{
+    Duration,
+    Instant
+};
+use crate::app::input::{
+    PointerId,
+    LONG_PRESS_DURATION,
+    MOVE_THRESHOLD
+};
+use crate::app::touch::MultiTouch;
+use crate::utils::theme::{
+    self,
+    KeyClass
+};
+
+static PRESS_DURATION: Duration = Duration::from_millis(60); // time a press takes to show
+static RELEASE_DURATION: Duration = Duration::from_millis(150); // time a press takes to fade once let go
+static PRESS_SHRINK: f32 = 0.06; // share of its size the key shrinks by when pressed
+static PREVIEW_SCALE: f32 = 1.5; // the preview above a pressed key magnifies its content by this
+static KEY_GAP: f32 = 2.0; // pixels between the fills of neighbouring keys
 
 
 // https://giesch.dev/iced-hoverable/
@@ -46,6 +71,29 @@ where
     on_resize: Option<Box<dyn Fn(Size) -> Message + 'a>>,
     on_show: Option<Box<dyn Fn(Size) -> Message + 'a>>,
     on_bounds: Option<Box<dyn Fn(Rectangle) -> Message + 'a>>,
+    preview: bool, // shows the content magnified above the key while it is pressed
+    latch: Latch,
+    class: KeyClass, // picks the fill from the theme
+}
+
+/// How a modifier key, eg. shift, is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Latch {
+    #[default]
+    Off,
+    Latched, // applies to the next key, then lets go
+    Locked, // applies until the modifier is tapped again
+}
+
+impl Latch {
+    /// The next state when the modifier is tapped.
+    pub fn next(self) -> Latch {
+        match self {
+            Latch::Off => Latch::Latched,
+            Latch::Latched => Latch::Locked,
+            Latch::Locked => Latch::Off,
+        }
+    }
 }
 
 
@@ -78,7 +126,10 @@ where
             on_press: None,
             on_resize: None,
             on_show: None,
-            on_bounds: None
+            on_bounds: None,
+            preview: false,
+            latch: Latch::Off,
+            class: KeyClass::Letter,
         }
     }
 
@@ -117,6 +168,24 @@ where
     }
 
 
+    /// Shows the content magnified above the [`Key`] while it is pressed, so it can be seen under a finger.
+    pub fn preview(mut self) -> Self {
+        self.preview = true;
+        self
+    }
+
+    /// Sets the kind of [`Key`], which picks its fill from the theme.
+    pub fn class(mut self, class: KeyClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// Shows the [`Key`] as a modifier that is latched or locked.
+    pub fn latch(mut self, latch: Latch) -> Self {
+        self.latch = latch;
+        self
+    }
+
     /// Sets the message that will be produced when the [`Key`] is pressed.
     ///
     /// Unless `on_press` is called, the [`Key`] will be disabled.
@@ -156,10 +225,87 @@ where
 // State is the internal state of the button
 #[derive(Debug, Clone, Default)]
 struct State {
-    is_pressed: bool,
+    press: Option<KeyPress>, // a pointer pressed on the key that is still a tap
+    fingers: MultiTouch, // every finger on the screen, so a key isn't pressed during a multi-finger gesture
+    released: Option<(Instant, f32)>, // when the last press was let go, and how far it had shown, for the fade
     last_size: Option<Size>,
 }
 
+/// A pointer pressed on the key, it stays a tap until it moves or is held like the `InputHandler` decides.
+#[derive(Debug, Clone)]
+struct KeyPress {
+    id: PointerId,
+    start: Point,
+    instant: Instant,
+}
+
+impl<Message, Theme, Renderer> Key<'_, Message, Theme, Renderer>
+where
+    Renderer: iced_core::Renderer,
+    Message: Clone,
+{
+    fn publish_press(&self, shell: &mut Shell<'_, Message>) {
+        if let Some(on_press) = &self.on_press {
+            shell.publish(on_press.get());
+        }
+    }
+}
+
+impl State {
+    /// Starts a tap, unless the key is disabled or another pointer is already down.
+    fn press_at(&mut self, enabled: bool, id: PointerId, position: Point) {
+        if !enabled || self.press.is_some() {
+            return
+        }
+        self.press = Some(KeyPress {
+            id,
+            start: position,
+            instant: Instant::now(),
+        });
+    }
+
+    /// A pointer that moves too far from where it was pressed is a glide, so the key is let go.
+    fn move_to(&mut self, id: PointerId, position: Point) {
+        if self.press.as_ref().is_some_and(|press| press.id == id && press.start.distance(position) > MOVE_THRESHOLD) {
+            self.let_go();
+        }
+    }
+
+    /// Lets go of the key, returns true if it was tapped rather than held for a long press.
+    fn release(&mut self, id: PointerId) -> bool {
+        let Some(instant) = self.press.as_ref().filter(|press| press.id == id).map(|press| press.instant) else {
+            return false
+        };
+        self.let_go();
+        instant.elapsed() < LONG_PRESS_DURATION
+    }
+
+    /// Drops the press, it fades from however far it had shown.
+    fn let_go(&mut self) {
+        let level = self.level();
+        if self.press.take().is_some() {
+            self.released = Some((Instant::now(), level));
+        }
+    }
+
+    /// How far the press shows, 0 when the key is up, 1 when it is fully down.
+    fn level(&self) -> f32 {
+        match (&self.press, self.released) {
+            (Some(press), _) => (press.instant.elapsed().as_secs_f32() / PRESS_DURATION.as_secs_f32()).min(1.0),
+            (None, Some((instant, from))) => (from * (1.0 - instant.elapsed().as_secs_f32() / RELEASE_DURATION.as_secs_f32())).max(0.0),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// True while the press is showing or fading, so the key keeps being redrawn.
+    fn is_animating(&self) -> bool {
+        match &self.press {
+            Some(press) => press.instant.elapsed() < PRESS_DURATION,
+            None => self.released.is_some_and(|(instant, _)| instant.elapsed() < RELEASE_DURATION),
+        }
+    }
+}
+
 
 /// The meat & potatoes of the widget
 impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> 
@@ -250,72 +396,62 @@ where
                     //shell.publish(closure);
                 }
             }
-        }
-
-
-        // TODO: key is gobbling our events. Need to fix this to work with gestures - short tap vs glide gesture
-
 
-        // update sub component widget
-        // self.content.as_widget_mut().update(
-        //     &mut tree.children[0],
-        //     event,
-        //     layout.children().next().unwrap(),
-        //     cursor,
-        //     renderer,
-        //     clipboard,
-        //     shell,
-        //     viewport,
-        // );
+            // keep drawing while the press shows or fades
+            if state.is_animating() {
+                shell.request_redraw();
+            }
+        }
 
-        // // return if event captured by widget
-        // if shell.is_event_captured() {
-        //     return;
-        // }
 
-        // which event
+        // the key only fires for taps, glides and long presses over it are handled by the InputHandler,
+        // so events are never captured here
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        let was_pressed = state.press.is_some();
         match event {
-            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
-            | Event::Touch(touch::Event::FingerPressed { .. }) => {
-                if self.on_press.is_some() {
-                    let bounds = layout.bounds();
-
-                    if cursor.is_over(bounds) {
-                        let state = tree.state.downcast_mut::<State>();
-
-                        state.is_pressed = true;
-
-                        // disable for gestures
-                        //shell.capture_event();
-                    }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(position) = cursor.position_over(bounds) {
+                    state.press_at(self.on_press.is_some(), PointerId::Mouse, position);
                 }
             }
-            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
-            | Event::Touch(touch::Event::FingerLifted { .. }) => {
-                if let Some(on_press) = &self.on_press {
-                    let state = tree.state.downcast_mut::<State>();
-
-                    if state.is_pressed {
-                        state.is_pressed = false;
-
-                        let bounds = layout.bounds();
-
-                        if cursor.is_over(bounds) {
-                            shell.publish(on_press.get());
-                        }
-
-                        // disable for gestures
-                        //shell.capture_event();
-                    }
+            Event::Touch(touch::Event::FingerPressed { id, position }) => {
+                let _ = state.fingers.press(id.0, *position);
+                match state.fingers.is_multi() {
+                    true => state.let_go(),
+                    false if bounds.contains(*position) => state.press_at(self.on_press.is_some(), PointerId::Finger(id.0), *position),
+                    false => {}
                 }
             }
-            Event::Touch(touch::Event::FingerLost { .. }) => {
-                let state = tree.state.downcast_mut::<State>();
-
-                state.is_pressed = false;
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                state.move_to(PointerId::Mouse, *position);
+            }
+            Event::Touch(touch::Event::FingerMoved { id, position }) => {
+                let _ = state.fingers.move_to(id.0, *position);
+                state.move_to(PointerId::Finger(id.0), *position);
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.release(PointerId::Mouse) && cursor.is_over(bounds) {
+                    self.publish_press(shell);
+                }
+            }
+            Event::Touch(touch::Event::FingerLifted { id, position }) => {
+                let _ = state.fingers.lift(id.0);
+                if state.release(PointerId::Finger(id.0)) && bounds.contains(*position) {
+                    self.publish_press(shell);
+                }
+            }
+            Event::Touch(touch::Event::FingerLost { id, .. }) => {
+                let _ = state.fingers.lift(id.0);
+                if state.press.as_ref().is_some_and(|press| press.id == PointerId::Finger(id.0)) {
+                    state.let_go();
+                }
             }
             _ => {}
         }
+        if state.press.is_some() != was_pressed {
+            shell.request_redraw();
+        }
 
         // more stuff from button.rs
         // let current_status = if self.on_press.is_none() {
@@ -337,32 +473,6 @@ where
         // } else if self.status.is_some_and(|status| status != current_status) {
         //     shell.request_redraw();
         // }
-
-
-
-        /*
-
-        // todo: Brons help, how do i get some sort of animation loop here for a key?
-        // for animation: https://github.com/iced-rs/iced/blob/master/examples/loading_spinners/src/circular.rs
-        // Fab: check the link above. Update function is for recieving messages on the message bus. So this will be for storing states.
-        // use the draw function to draw any animations. Use the macro "info!("HELP!");" to print to console
-        // or info!("{:?}", some_value); to print out values.. pop it in the update and draw functions to see how they operate :)
-        // Fab: this should work now were on 0.14? I guess!
-        
-        */
-        /* 
-        let state = tree.state.downcast_mut::<State>();
-
-        if let Event::Window(window::Event::RedrawRequested(now)) = event {
-            state.animation = state.animation.timed_transition(
-                self.cycle_duration,
-                self.rotation_duration,
-                *now,
-            );
-
-            state.cache.clear();
-            shell.request_redraw();
-        }*/
     }
 
 
@@ -378,18 +488,60 @@ where
     ) {
         let bounds = layout.bounds();
         let content_layout = layout.children().next().unwrap();
-        let is_mouse_over = cursor.is_over(bounds);
+        let key_state = state.state.downcast_ref::<State>();
+        let palette = theme::get();
+        let text_color = match self.on_press.is_some() {
+            true => palette.text, //style.text_color,
+            false => palette.text.scale_alpha(0.35), // disabled
+        };
 
-        
-        if is_mouse_over {
-            // draw background quad
+        let fill = palette.key_fill(self.class);
+        if fill.a > 0.0 {
             renderer.fill_quad(
                 Quad {
-                    bounds: bounds,
-                    border: border::rounded(10), //Border::default(),
+                    bounds: inset(bounds, KEY_GAP),
+                    border: border::rounded(palette.corner_radius),
                     shadow: Shadow::default(),
                 },
-    Color::from_rgba(1.0, 1.0, 1.0, 0.15),
+                fill,
+            );
+        }
+
+        // a latched modifier is outlined, a locked one is filled
+        match self.latch {
+            Latch::Off => {}
+            Latch::Latched => renderer.fill_quad(
+                Quad {
+                    bounds: inset(bounds, KEY_GAP),
+                    border: Border {
+                        color: palette.accent,
+                        width: 2.0,
+                        radius: palette.corner_radius.into(),
+                    },
+                    shadow: Shadow::default(),
+                },
+                Color::TRANSPARENT,
+            ),
+            Latch::Locked => renderer.fill_quad(
+                Quad {
+                    bounds: inset(bounds, KEY_GAP),
+                    border: border::rounded(palette.corner_radius),
+                    shadow: Shadow::default(),
+                },
+                palette.accent,
+            ),
+        }
+
+        // only shown for taps, a glide passing over the key doesn't press it
+        let level = key_state.level();
+        if level > 0.0 {
+            renderer.fill_quad(
+                Quad {
+                    bounds: scaled(inset(bounds, KEY_GAP), 1.0 - PRESS_SHRINK * level),
+                    border: border::rounded(palette.corner_radius), //Border::default(),
+                    shadow: Shadow::default(),
+                },
+                palette.pressed.scale_alpha(level),
             );
         }
 
@@ -400,13 +552,53 @@ where
             renderer,
             theme,
             &renderer::Style {
-                text_color: Color::from_rgb(1.0, 1.0, 1.0), //style.text_color,
+                text_color,
             },
             content_layout, //content_layout,
             cursor,
             &viewport,
         );
 
+        // the preview sits above the key, in its own layer so the keys drawn after it don't cover it
+        if self.preview && key_state.press.is_some() {
+            let size = Size::new(bounds.width * PREVIEW_SCALE, bounds.height * PREVIEW_SCALE);
+            let x = (bounds.center_x() - size.width / 2.0).clamp(viewport.x, (viewport.x + viewport.width - size.width).max(viewport.x));
+            let y = (bounds.y - size.height).max(viewport.y);
+            let bubble = Rectangle::new(Point::new(x, y), size);
+            let content = content_layout.bounds();
+            let translation = Transformation::translate(
+                bubble.center_x() - content.center_x() * PREVIEW_SCALE,
+                bubble.center_y() - content.center_y() * PREVIEW_SCALE,
+            );
+            renderer.with_layer(*viewport, |renderer| {
+                renderer.fill_quad(
+                    Quad {
+                        bounds: bubble,
+                        border: border::rounded(palette.corner_radius),
+                        shadow: Shadow {
+                            color: Color::from_rgba(0.0, 0.0, 0.0, 0.4),
+                            offset: Vector::new(0.0, 2.0),
+                            blur_radius: 6.0,
+                        },
+                    },
+                    palette.preview,
+                );
+                renderer.with_transformation(translation * Transformation::scale(PREVIEW_SCALE), |renderer| {
+                    self.content.as_widget().draw(
+                        &state.children[0],
+                        renderer,
+                        theme,
+                        &renderer::Style {
+                            text_color,
+                        },
+                        content_layout,
+                        cursor,
+                        viewport,
+                    );
+                });
+            });
+        }
+
         // draw text manually
         // renderer.fill_text(
         //     Text {
@@ -436,7 +628,7 @@ where
         _viewport: &Rectangle,
         _renderer: &Renderer,
     ) -> mouse::Interaction {
-        if cursor.is_over(layout.bounds()) {
+        if self.on_press.is_some() && cursor.is_over(layout.bounds()) {
             mouse::Interaction::Pointer
         } else {
             mouse::Interaction::Idle
@@ -500,3 +692,17 @@ where
     }
 }
 
+
+/// The bounds shrunk by the amount on every side.
+fn inset(bounds: Rectangle, amount: f32) -> Rectangle {
+    Rectangle::new(
+        Point::new(bounds.x + amount, bounds.y + amount),
+        Size::new((bounds.width - 2.0 * amount).max(0.0), (bounds.height - 2.0 * amount).max(0.0)),
+    )
+}
+
+/// The bounds scaled about their center.
+fn scaled(bounds: Rectangle, scale: f32) -> Rectangle {
+    let size = Size::new(bounds.width * scale, bounds.height * scale);
+    Rectangle::new(Point::new(bounds.center_x() - size.width / 2.0, bounds.center_y() - size.height / 2.0), size)
+}
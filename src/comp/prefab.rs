@@ -1,24 +1,87 @@
-use iced::{widget::text, Renderer, Theme};
+use iced::{widget::{row, svg, text, Svg, Text}, Length, Renderer, Theme};
 
 use crate::app::*;
+use crate::utils::*;
+use crate::utils::theme::KeyClass;
 use super::*;
 
+pub static BACKSPACE: &str = "Backspace"; // label the backspace key reports its bounds with
+
+
+/// Text for a key, in the font of the theme.
+fn label<'a>(val: String) -> Text<'a, Theme, Renderer> {
+    text(val).font(theme::get().font)
+}
+
+/// An icon for a key, in the text colour of the theme.
+fn icon<'a>(bytes: &'static [u8], size: f32) -> Svg<'a, Theme> {
+    svg(svg::Handle::from_memory(bytes))
+        .width(Length::Fixed(size))
+        .height(Length::Fixed(size))
+        .style(|_theme, _status| svg::Style {
+            color: Some(theme::get().text),
+        })
+}
+
 
 /// Creates a new [`Key`] with the given content.
 pub fn key_glide(val: &str) -> Key<'_, main_app::Message, Theme, Renderer> {
-    let content = text(val.to_string()).center();
+    let content = label(val.to_string()).center();
     Key::new(content)
         .on_press(main_app::Message::Debug(val.to_string()))
         .on_bounds(|bounds| main_app::Message::SearchHandler(search::Message::Update(val.to_string(), bounds)))
+        .preview()
         .into()
 }
 
 
 /// Creates a new [`Key`] with the given content.
 pub fn key_from_str(val: &str) -> Key<'_, main_app::Message, Theme, Renderer> {
-    let content= text(val.to_string()).center();
+    let content= label(val.to_string()).center();
     Key::new(content)
         .on_press(main_app::Message::Debug(val.to_string()))
         .on_bounds(|bounds| main_app::Message::SearchHandler(search::Message::Update(val.to_string(), bounds)))
         .into()
-}
\ No newline at end of file
+}
+
+
+/// Creates a new [`Key`] for the suggestion bar, which commits the word when pressed.
+pub fn key_suggestion(val: &str) -> Key<'_, main_app::Message, Theme, Renderer> {
+    let content = label(val.to_string()).center();
+    Key::new(content)
+        .on_press(main_app::Message::SearchHandler(search::Message::Pick(val.to_string())))
+        .class(KeyClass::Suggestion)
+}
+
+/// Creates a new [`Key`] for the suggestion bar, showing a tapped word before it was autocorrected.
+/// Pressing it puts the word back as it was tapped.
+pub fn key_undo_correction(original: &str) -> Key<'_, main_app::Message, Theme, Renderer> {
+    let content = label(format!("\"{original}\"")).center();
+    Key::new(content)
+        .on_press(main_app::Message::SearchHandler(search::Message::UndoCorrection))
+        .class(KeyClass::Suggestion)
+}
+
+/// Creates a new [`Key`] that shows the active language and switches to the next one when pressed.
+pub fn key_language<'a>(tag: String) -> Key<'a, main_app::Message, Theme, Renderer> {
+    let content = row![icon(globals::ICON_GLOBE, 16.), label(tag)].spacing(4);
+    Key::new(content)
+        .on_press(main_app::Message::SearchHandler(search::Message::NextLanguage))
+        .class(KeyClass::Function)
+}
+
+/// Creates a new [`Key`] for shift, tapping it latches it for the next letter or word, then locks it, then lets go.
+pub fn key_shift<'a>(shift: Latch) -> Key<'a, main_app::Message, Theme, Renderer> {
+    Key::new(label(String::from("⇧")).center())
+        .on_press(main_app::Message::OutputHandler(output::Message::Shift))
+        .latch(shift)
+        .class(KeyClass::Modifier)
+}
+
+/// Creates a new [`Key`] for backspace, which is tapped like a letter so it is handled by the search.
+pub fn key_backspace<'a>() -> Key<'a, main_app::Message, Theme, Renderer> {
+    Key::new(icon(globals::ICON_BACKSPACE, 20.))
+        .on_press(main_app::Message::Debug(BACKSPACE.to_string()))
+        .on_bounds(|bounds| main_app::Message::SearchHandler(search::Message::Update(BACKSPACE.to_string(), bounds)))
+        .class(KeyClass::Function)
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+/// A prefix that joins onto words starting with certain letters, eg. "l'" in "l'eau".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Elision {
+    pub prefix: String, // as it is written, eg. "qu'"
+    pub before: String, // folded letters that the word after the prefix can start with
+}
+
+impl Elision {
+    /// True if the prefix can join onto the word.
+    pub fn allows(&self, word: &str) -> bool {
+        unicode::fold(word).chars().next().is_some_and(|c| self.before.contains(c))
+    }
+}
+
+/// Words written with an apostrophe, which are glided or tapped without it,
+/// as words are matched to keys with their punctuation removed.
+/// Read from a text file per language, one rule per line:
+/// ```text
+/// dont don't
+/// it's
+/// elide l' aeiouyh
+/// ```
+/// A pair replaces the bare word, which is only ever a misspelling of the contraction.
+/// A single word is added alongside the word it folds to, and the language model picks between them.
+/// An elision joins its prefix onto any word starting with one of the letters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Contractions {
+    pub replacements: Vec<(String, String)>, // bare word, written word
+    pub words: Vec<String>,
+    pub elisions: Vec<Elision>,
+}
+
+impl Contractions {
+    pub fn parse(text: &str) -> Self {
+        let mut contractions = Contractions::default();
+        for line in text.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["elide", prefix, before] => contractions.elisions.push(Elision {
+                    prefix: unicode::normalize(prefix),
+                    before: unicode::fold(before),
+                }),
+                [bare, written] => contractions.replacements.push((bare.to_string(), unicode::normalize(written))),
+                [written] => contractions.words.push(unicode::normalize(written)),
+                _ => warn!("Skipping malformed contraction line: {:?}", line),
+            }
+        }
+        contractions
+    }
+
+    /// Adds the contractions to the dictionary.
+    pub fn apply(&self, trie: &mut WordTrie) {
+        for (bare, written) in self.replacements.iter() {
+            trie.remove(bare);
+            trie.insert(written);
+        }
+        for word in self.words.iter() {
+            trie.insert(word);
+        }
+        for elision in self.elisions.iter() {
+            trie.add_elision(elision.clone());
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::{
+    HashMap,
+    HashSet
+};
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use super::*;
+
+pub static HUNSPELL_DIR: &str = "/usr/share/hunspell";
+static CACHE_DIR: &str = "hunspell"; // expanded word lists, in the xdg cache home
+static CACHE_VERSION: u32 = 2; // bump when the expansion changes, to invalidate old caches
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlagMode {
+    Char, // one character per flag, the default
+    Long, // two characters per flag
+    Num, // comma separated numbers
+    Utf8, // one unicode character per flag
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Any,
+    Char(char),
+    Set(Vec<char>, bool), // characters, negated
+}
+
+impl Condition {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Condition::Any => true,
+            Condition::Char(expected) => *expected == c,
+            Condition::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+/// A single prefix or suffix rule, eg. `SFX D y ied [^aeiou]y`.
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<Condition>,
+}
+
+#[derive(Debug, Clone)]
+struct AffixGroup {
+    cross_product: bool, // can combine with affixes of the other kind
+    rules: Vec<AffixRule>,
+}
+
+/// The parts of a Hunspell `.aff` file needed to expand a word list.
+/// Compounding, replacement tables and morphology are ignored.
+#[derive(Debug, Clone)]
+struct Affixes {
+    flag_mode: FlagMode,
+    aliases: Vec<Vec<u32>>, // AF flag sets, referenced by number from the .dic file
+    prefixes: HashMap<u32, AffixGroup>,
+    suffixes: HashMap<u32, AffixGroup>,
+    hidden: Vec<u32>, // NEEDAFFIX, ONLYINCOMPOUND and FORBIDDENWORD, the bare word is not a word
+}
+
+impl Affixes {
+    fn parse(text: &str) -> Self {
+        let mut affixes = Affixes {
+            flag_mode: FlagMode::Char,
+            aliases: Vec::new(),
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+            hidden: Vec::new(),
+        };
+
+        // the flag mode changes how every other flag is read, so find it first
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            if fields.next() == Some("FLAG") {
+                affixes.flag_mode = match fields.next() {
+                    Some("long") => FlagMode::Long,
+                    Some("num") => FlagMode::Num,
+                    Some("UTF-8") => FlagMode::Utf8,
+                    _ => FlagMode::Char,
+                };
+            }
+        }
+
+        // the first AF line is the number of aliases
+        let mut alias_count_seen = false;
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["AF", flags, ..] => {
+                    match alias_count_seen {
+                        true => affixes.aliases.push(affixes.parse_flags(flags)),
+                        false => alias_count_seen = true,
+                    }
+                }
+                ["NEEDAFFIX", flag, ..] | ["ONLYINCOMPOUND", flag, ..] | ["FORBIDDENWORD", flag, ..] => {
+                    affixes.hidden.extend(affixes.parse_flags(flag));
+                }
+                [kind @ ("PFX" | "SFX"), flag, cross_product, count] if count.parse::<usize>().is_ok() => {
+                    let Some(&flag) = affixes.parse_flags(flag).first() else {
+                        continue
+                    };
+                    let group = AffixGroup {
+                        cross_product: cross_product == "Y",
+                        rules: Vec::new(),
+                    };
+                    match kind {
+                        "PFX" => affixes.prefixes.insert(flag, group),
+                        _ => affixes.suffixes.insert(flag, group),
+                    };
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, condition, ..] => {
+                    let Some(&flag) = affixes.parse_flags(flag).first() else {
+                        continue
+                    };
+                    // continuation flags after the slash are not followed
+                    let add = add.split('/').next().unwrap_or_default();
+                    let rule = AffixRule {
+                        strip: if strip == "0" { String::new() } else { strip.to_string() },
+                        add: if add == "0" { String::new() } else { add.to_string() },
+                        condition: parse_condition(condition),
+                    };
+                    let groups = match kind {
+                        "PFX" => &mut affixes.prefixes,
+                        _ => &mut affixes.suffixes,
+                    };
+                    match groups.get_mut(&flag) {
+                        Some(group) => group.rules.push(rule),
+                        None => warn!("Skipping hunspell affix rule without a header: {:?}", line),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        affixes
+    }
+
+    fn parse_flags(&self, flags: &str) -> Vec<u32> {
+        match self.flag_mode {
+            FlagMode::Char | FlagMode::Utf8 => flags.chars().map(|c| c as u32).collect(),
+            FlagMode::Long => flags
+                .chars()
+                .collect::<Vec<char>>()
+                .chunks(2)
+                .map(|pair| pair.iter().fold(0, |flag, c| (flag << 16) | *c as u32))
+                .collect(),
+            FlagMode::Num => flags.split(',').filter_map(|flag| flag.trim().parse().ok()).collect(),
+        }
+    }
+
+    /// Flags of a .dic entry, which may be an alias number when the .aff file has AF lines.
+    fn entry_flags(&self, flags: &str) -> Vec<u32> {
+        if !self.aliases.is_empty() {
+            if let Ok(index) = flags.parse::<usize>() {
+                return self.aliases.get(index.wrapping_sub(1)).cloned().unwrap_or_default()
+            }
+        }
+        self.parse_flags(flags)
+    }
+
+    /// Every form of the word its flags allow: the word itself, one prefix, one suffix,
+    /// and a prefix with a suffix when both are marked as cross product.
+    fn expand(&self, word: &str, flags: &[u32], out: &mut Vec<String>) {
+        if !flags.iter().any(|flag| self.hidden.contains(flag)) {
+            out.push(word.to_string());
+        }
+
+        let mut cross_suffixed = Vec::new();
+        for group in flags.iter().filter_map(|flag| self.suffixes.get(flag)) {
+            for rule in group.rules.iter() {
+                let Some(stem) = word.strip_suffix(rule.strip.as_str()) else {
+                    continue
+                };
+                if !matches_end(word, &rule.condition) {
+                    continue
+                }
+                let form = format!("{stem}{}", rule.add);
+                if group.cross_product {
+                    cross_suffixed.push(form.clone());
+                }
+                out.push(form);
+            }
+        }
+
+        for group in flags.iter().filter_map(|flag| self.prefixes.get(flag)) {
+            for rule in group.rules.iter() {
+                if word.strip_prefix(rule.strip.as_str()).is_none() || !matches_start(word, &rule.condition) {
+                    continue
+                }
+                out.push(format!("{}{}", rule.add, &word[rule.strip.len()..]));
+
+                if !group.cross_product {
+                    continue
+                }
+                for form in cross_suffixed.iter() {
+                    if let Some(rest) = form.strip_prefix(rule.strip.as_str()) {
+                        out.push(format!("{}{rest}", rule.add));
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+/// Parses an affix condition, a cut down regex of characters, `.` and `[...]` or `[^...]` sets.
+fn parse_condition(condition: &str) -> Vec<Condition> {
+    if condition == "." {
+        return Vec::new()
+    }
+
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(Condition::Any),
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|c| *c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                parts.push(Condition::Set(set, negated));
+            }
+            c => parts.push(Condition::Char(c)),
+        }
+    }
+    parts
+}
+
+fn matches_start(word: &str, condition: &[Condition]) -> bool {
+    let chars: Vec<char> = word.chars().take(condition.len()).collect();
+    chars.len() == condition.len() && condition.iter().zip(chars).all(|(part, c)| part.matches(c))
+}
+
+fn matches_end(word: &str, condition: &[Condition]) -> bool {
+    let chars: Vec<char> = word.chars().rev().take(condition.len()).collect();
+    chars.len() == condition.len() && condition.iter().rev().zip(chars).all(|(part, c)| part.matches(c))
+}
+
+/// Reads a dictionary file, which is UTF-8 unless the .aff file says otherwise.
+/// ISO8859 files are read as latin-1, which covers the common western languages.
+fn decode(bytes: Vec<u8>, encoding: &str) -> String {
+    match encoding.to_uppercase().as_str() {
+        "" | "UTF-8" | "UTF8" => String::from_utf8(bytes).unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        encoding => {
+            if !encoding.starts_with("ISO8859") && !encoding.starts_with("ISO-8859") {
+                warn!("Hunspell encoding {} is not supported, reading it as latin-1", encoding);
+            }
+            bytes.into_iter().map(|byte| byte as char).collect()
+        }
+    }
+}
+
+/// Finds the encoding from the SET line of an .aff file.
+fn encoding(aff: &[u8]) -> String {
+    String::from_utf8_lossy(aff)
+        .lines()
+        .find_map(|line| line.strip_prefix("SET "))
+        .map(|encoding| encoding.trim().to_string())
+        .unwrap_or_default()
+}
+
+
+/// Expands a Hunspell dictionary into a word list.
+/// Words are folded to lowercase, to match the keys, and anything that can't be glided is dropped.
+pub fn expand(dic: &str, aff: &str) -> Vec<String> {
+    let affixes = Affixes::parse(aff);
+    let mut forms = Vec::new();
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+
+    // the first line is the number of entries
+    for line in dic.lines().skip(1) {
+        // morphological fields follow a tab or space
+        let Some(entry) = line.split(['\t', ' ']).next().filter(|entry| !entry.is_empty()) else {
+            continue
+        };
+        let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+
+        forms.clear();
+        affixes.expand(word, &affixes.entry_flags(flags), &mut forms);
+        for form in forms.drain(..) {
+            // composed, so accents aren't separate marks
+            let form = unicode::normalize(&form);
+            if !form.chars().all(|c| c.is_alphabetic() || c == '\'') {
+                continue
+            }
+            let form = form.to_lowercase();
+            if seen.insert(form.clone()) {
+                words.push(form);
+            }
+        }
+    }
+    words
+}
+
+/// Loads the expanded word list for a dictionary, from the cache when the .dic and .aff files haven't changed.
+pub fn load(tag: &str, dic: &Path, aff: &Path) -> io::Result<String> {
+    let cache = xdg::BaseDirectories::with_prefix("surfboard")
+        .ok()
+        .and_then(|dirs| dirs.place_cache_file(format!("{CACHE_DIR}/{tag}.txt")).ok());
+    if cache.is_none() {
+        error!("Unable to find the xdg cache dir, hunspell dictionaries will be expanded every launch");
+    }
+    load_cached(tag, dic, aff, cache.as_deref())
+}
+
+/// Loads the expanded word list, from the cache file if it was expanded from the same files.
+fn load_cached(tag: &str, dic: &Path, aff: &Path, cache: Option<&Path>) -> io::Result<String> {
+    let stamp = format!("# {} {} {}", CACHE_VERSION, file_stamp(dic)?, file_stamp(aff)?);
+
+    // the first line of the cache records which files it was expanded from
+    let cached = cache
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|cached| cached.split_once('\n').filter(|(header, _)| *header == stamp).map(|(_, words)| words.to_string()));
+    if let Some(words) = cached {
+        return Ok(words)
+    }
+
+    let now = std::time::Instant::now();
+    let aff = std::fs::read(aff)?;
+    let encoding = encoding(&aff);
+    let words = expand(&decode(std::fs::read(dic)?, &encoding), &decode(aff, &encoding));
+    info!("Hunspell dictionary \"{}\" expanded to {} words in {}ms", tag, words.len(), now.elapsed().as_millis());
+
+    let words = words.join("\n");
+    if let Some(path) = cache {
+        if let Err(err) = std::fs::write(path, format!("{stamp}\n{words}")) {
+            error!("Failed to cache hunspell dictionary \"{}\", with error: {}", path.display(), err);
+        }
+    }
+    Ok(words)
+}
+
+/// Identifies a version of a file by its modified time and size.
+fn file_stamp(path: &Path) -> io::Result<String> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+    Ok(format!("{}:{}", modified, metadata.len()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static AFF: &str = "\
+SET UTF-8
+NEEDAFFIX X
+PFX A Y 1
+PFX A 0 re .
+PFX I N 1
+PFX I 0 un .
+PFX O Y 1
+PFX O a o a
+SFX D Y 3
+SFX D y ied [^aeiou]y
+SFX D 0 ed [aeiou]y
+SFX D 0 d e
+SFX S N 1
+SFX S 0 s .
+";
+
+    static DIC: &str = "\
+6
+try/AD
+play/AD
+bake/D
+do/IS
+walk/XS
+ax/O
+";
+
+    // prefixes, suffixes with strip and condition rules, cross products,
+    // and a word that is only a word with an affix
+    static WORDS: [&str; 16] = [
+        "try", "tried", "retry", "retried",
+        "play", "played", "replay", "replayed",
+        "bake", "baked",
+        "do", "dos", "undo",
+        "walks",
+        "ax", "ox",
+    ];
+
+    #[test]
+    fn expands_affixes() {
+        assert_eq!(expand(DIC, AFF), WORDS);
+    }
+
+    #[test]
+    fn conditions_match_the_ends_of_the_word() {
+        let condition = parse_condition("[^aeiou]y");
+        assert!(matches_end("try", &condition));
+        assert!(!matches_end("play", &condition));
+        assert!(!matches_end("y", &condition));
+        assert!(matches_start("ax", &parse_condition("a")));
+        assert!(matches_start("ax", &parse_condition(".")));
+    }
+
+    #[test]
+    fn caches_the_expanded_words_until_the_files_change() {
+        let dir = std::env::temp_dir().join(format!("surfboard-hunspell-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (dic, aff, cache) = (dir.join("test.dic"), dir.join("test.aff"), dir.join("test.txt"));
+        std::fs::write(&dic, DIC).unwrap();
+        std::fs::write(&aff, AFF).unwrap();
+
+        let words = load_cached("test", &dic, &aff, Some(&cache)).unwrap();
+        assert!(words.lines().eq(WORDS));
+
+        // the cache is read while the files are unchanged
+        let cached = std::fs::read_to_string(&cache).unwrap();
+        let (header, _) = cached.split_once('\n').unwrap();
+        std::fs::write(&cache, format!("{header}\ncached")).unwrap();
+        assert_eq!(load_cached("test", &dic, &aff, Some(&cache)).unwrap(), "cached");
+
+        // a changed dictionary is expanded again
+        std::fs::write(&dic, format!("{DIC}talk\n")).unwrap();
+        let words = load_cached("test", &dic, &aff, Some(&cache)).unwrap();
+        assert!(words.lines().any(|word| word == "talk"));
+        assert!(std::fs::read_to_string(&cache).unwrap().ends_with("talk"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+use std::path::PathBuf;
+use crate::utils::*;
+use super::*;
+
+pub static BUILTIN_LANGUAGE: &str = "en";
+static DICTIONARY_DIR: &str = "dictionaries";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DictionarySource {
+    Builtin, // res/dictionary.txt, embedded in the binary
+    File(PathBuf), // a word list with one word per line
+    Hunspell(PathBuf, PathBuf), // .dic and .aff files, expanded on load
+}
+
+/// A language that has a dictionary available, identified by its tag, eg. "en" or "de".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Language {
+    pub tag: String,
+    pub source: DictionarySource,
+}
+
+impl Language {
+    pub fn builtin() -> Self {
+        Language {
+            tag: BUILTIN_LANGUAGE.to_string(),
+            source: DictionarySource::Builtin,
+        }
+    }
+
+    /// Reads the word list, empty if it can't be read.
+    pub fn words(&self) -> Cow<'static, str> {
+        match &self.source {
+            DictionarySource::Builtin => Cow::Borrowed(globals::DICTIONARY),
+            DictionarySource::File(path) => match std::fs::read_to_string(path) {
+                Ok(words) => Cow::Owned(words),
+                Err(err) => {
+                    error!("Failed to load dictionary \"{}\", with error: {}", path.display(), err);
+                    Cow::Borrowed("")
+                }
+            },
+            DictionarySource::Hunspell(dic, aff) => match hunspell::load(&self.tag, dic, aff) {
+                Ok(words) => Cow::Owned(words),
+                Err(err) => {
+                    error!("Failed to load hunspell dictionary \"{}\", with error: {}", dic.display(), err);
+                    Cow::Borrowed("")
+                }
+            },
+        }
+    }
+
+    /// Contractions and elisions for the language, from `dictionaries/<tag>.contractions` alongside the dictionary,
+    /// or the builtin rules for the language, eg. "fr" for "fr_CA".
+    pub fn contractions(&self) -> Contractions {
+        let path = xdg::BaseDirectories::with_prefix("surfboard")
+            .ok()
+            .and_then(|dirs| dirs.find_data_file(format!("{DICTIONARY_DIR}/{}.contractions", self.tag)));
+        if let Some(path) = path {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => return Contractions::parse(&text),
+                Err(err) => error!("Failed to load contractions \"{}\", with error: {}", path.display(), err),
+            }
+        }
+
+        let primary = self.tag.split(['-', '_']).next().unwrap_or_default();
+        globals::CONTRACTIONS
+            .iter()
+            .find(|(tag, _)| *tag == primary)
+            .map(|(_, text)| Contractions::parse(text))
+            .unwrap_or_default()
+    }
+
+    /// The language model that sits alongside the dictionary, as `dictionaries/<tag>.ngram`.
+    /// The builtin language also accepts the model from `surfboard build-ngram`.
+    pub fn language_model_path(&self) -> Option<PathBuf> {
+        let dirs = xdg::BaseDirectories::with_prefix("surfboard").ok()?;
+        let path = dirs.find_data_file(format!("{DICTIONARY_DIR}/{}.ngram", self.tag));
+        if path.is_some() || self.tag != BUILTIN_LANGUAGE {
+            return path
+        }
+        dirs.find_data_file(globals::NGRAM_FILE)
+    }
+}
+
+
+/// Finds the dictionaries installed as `surfboard/dictionaries/<tag>.txt` in the user and system data dirs,
+/// then the Hunspell dictionaries in /usr/share/hunspell.
+/// User dictionaries override system ones with the same tag, word lists override Hunspell dictionaries,
+/// and a file for the builtin language overrides the builtin one.
+pub fn discover_languages() -> Vec<Language> {
+    let mut languages: Vec<Language> = Vec::new();
+
+    if let Ok(dirs) = xdg::BaseDirectories::with_prefix("surfboard") {
+        // the data home is listed first
+        for path in dirs.list_data_files(DICTIONARY_DIR) {
+            if path.extension().is_none_or(|extension| extension != "txt") {
+                continue
+            }
+            let Some(tag) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue
+            };
+            if !is_language_tag(tag) || languages.iter().any(|language| language.tag == tag) {
+                continue
+            }
+            languages.push(Language {
+                tag: tag.to_string(),
+                source: DictionarySource::File(path.clone()),
+            });
+        }
+    }
+
+    // hunspell dictionaries come as pairs, hyphenation patterns also use .dic but have no .aff
+    if let Ok(entries) = std::fs::read_dir(hunspell::HUNSPELL_DIR) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|extension| extension != "dic") {
+                continue
+            }
+            let aff = path.with_extension("aff");
+            let Some(tag) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue
+            };
+            if !aff.exists() || !is_language_tag(tag) || languages.iter().any(|language| language.tag == tag) {
+                continue
+            }
+            languages.push(Language {
+                tag: tag.to_string(),
+                source: DictionarySource::Hunspell(path.clone(), aff),
+            });
+        }
+    }
+
+    if !languages.iter().any(|language| language.tag == BUILTIN_LANGUAGE) {
+        languages.push(Language::builtin());
+    }
+    languages.sort_by(|a, b| a.tag.cmp(&b.tag));
+    languages
+}
+
+/// Tags are letters, digits and separators, eg. "en", "pt-BR" or "de_AT".
+pub fn is_language_tag(tag: &str) -> bool {
+    !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -0,0 +1,14 @@
+pub mod ngram;
+pub mod user_dictionary;
+pub mod language;
+pub mod hunspell;
+pub mod trie;
+pub mod unicode;
+pub mod contractions;
+
+// re-export
+pub use super::lang::ngram::*;
+pub use super::lang::user_dictionary::*;
+pub use super::lang::language::*;
+pub use super::lang::trie::*;
+pub use super::lang::contractions::*;
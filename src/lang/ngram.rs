@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{
+    self,
+    BufReader,
+    BufWriter,
+    Read,
+    Write
+};
+use std::path::Path;
+use super::*;
+
+static MAGIC: &[u8; 4] = b"SBNG";
+static VERSION: u32 = 1;
+static BACKOFF: f32 = 0.4; // stupid backoff penalty per order dropped
+static UNKNOWN_LOG_PROB: f32 = -8.0; // log10 probability of a word the model has never seen
+
+/// A bigram/trigram language model.
+/// Counts are stored against a shared vocabulary so the model stays compact on disk.
+/// Scores use "stupid backoff", which is cheap to query and good enough for ranking.
+#[derive(Debug, Clone, Default)]
+pub struct NgramModel {
+    vocab: Vec<String>,
+    index: HashMap<String, u32>,
+    unigrams: Vec<u32>,
+    bigrams: HashMap<(u32, u32), u32>,
+    trigrams: HashMap<(u32, u32, u32), u32>,
+    followers: HashMap<u32, Vec<u32>>, // words seen after a word
+    pair_followers: HashMap<(u32, u32), Vec<u32>>, // words seen after a pair of words
+    total: u64,
+}
+
+impl NgramModel {
+    /// Builds a model from a plain text corpus.
+    /// Bigrams and trigrams seen less than min_count times are dropped to keep the model small.
+    pub fn from_corpus(text: &str, min_count: u32) -> Self {
+        let mut model = NgramModel::default();
+
+        for sentence in tokenize(text) {
+            let ids: Vec<u32> = sentence.iter().map(|word| model.add_word(word)).collect();
+            for (i, id) in ids.iter().enumerate() {
+                model.unigrams[*id as usize] += 1;
+                model.total += 1;
+                if i > 0 {
+                    *model.bigrams.entry((ids[i - 1], *id)).or_insert(0) += 1;
+                }
+                if i > 1 {
+                    *model.trigrams.entry((ids[i - 2], ids[i - 1], *id)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        model.bigrams.retain(|_, count| *count >= min_count);
+        model.trigrams.retain(|_, count| *count >= min_count);
+        model.index_followers();
+        model
+    }
+
+    /// Counts a single word following the context, used to learn from what the user types.
+    pub fn learn(&mut self, context: &[String], word: &str) {
+        let word = word.to_lowercase();
+        let id = self.add_word(&word);
+        self.unigrams[id as usize] += 1;
+        self.total += 1;
+
+        let prev1 = context.last().map(|w| self.add_word(&w.to_lowercase()));
+        let prev2 = if context.len() > 1 { Some(self.add_word(&context[context.len() - 2].to_lowercase())) } else { None };
+
+        if let Some(p1) = prev1 {
+            let count = self.bigrams.entry((p1, id)).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                self.followers.entry(p1).or_default().push(id);
+            }
+
+            if let Some(p2) = prev2 {
+                let count = self.trigrams.entry((p2, p1, id)).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    self.pair_followers.entry((p2, p1)).or_default().push(id);
+                }
+            }
+        }
+    }
+
+    /// Returns the most likely words to follow the context, best first.
+    /// Falls back to the most common words when the context has not been seen.
+    pub fn predict(&self, context: &[String], limit: usize) -> Vec<(String, f32)> {
+        let prev1 = context.last().and_then(|w| self.id(w));
+        let prev2 = if context.len() > 1 { self.id(&context[context.len() - 2]) } else { None };
+
+        let mut ids: Vec<u32> = Vec::new();
+        if let (Some(p2), Some(p1)) = (prev2, prev1) {
+            ids.extend(self.pair_followers.get(&(p2, p1)).into_iter().flatten());
+        }
+        if let Some(p1) = prev1 {
+            ids.extend(self.followers.get(&p1).into_iter().flatten());
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() < limit {
+            let mut common: Vec<u32> = (0..self.vocab.len() as u32).collect();
+            if common.len() > limit {
+                common.select_nth_unstable_by(limit, |a, b| self.unigrams[*b as usize].cmp(&self.unigrams[*a as usize]));
+                common.truncate(limit);
+            }
+            ids.extend(common);
+        }
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut predictions: Vec<(String, f32)> = ids
+            .iter()
+            .map(|id| {
+                let word = &self.vocab[*id as usize];
+                (word.clone(), self.log_prob(context, word))
+            })
+            .collect();
+        predictions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        predictions.truncate(limit);
+        predictions
+    }
+
+    /// Loads a model written by [`NgramModel::save`].
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read_from(&mut reader)
+    }
+
+    /// Writes the model in the compact binary format.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn vocab_len(&self) -> usize {
+        self.vocab.len()
+    }
+
+    pub fn bigram_len(&self) -> usize {
+        self.bigrams.len()
+    }
+
+    pub fn trigram_len(&self) -> usize {
+        self.trigrams.len()
+    }
+
+    /// Returns the log10 probability of word following the context.
+    /// Only the last two words of the context are used.
+    pub fn log_prob(&self, context: &[String], word: &str) -> f32 {
+        let Some(word_id) = self.id(word) else {
+            return UNKNOWN_LOG_PROB
+        };
+
+        let prev1 = context.last().and_then(|w| self.id(w));
+        let prev2 = if context.len() > 1 { self.id(&context[context.len() - 2]) } else { None };
+
+        // trigram
+        if let (Some(p2), Some(p1)) = (prev2, prev1) {
+            if let (Some(count), Some(history)) = (self.trigrams.get(&(p2, p1, word_id)), self.bigrams.get(&(p2, p1))) {
+                return (*count as f32 / *history as f32).log10()
+            }
+        }
+
+        // bigram
+        if let Some(p1) = prev1 {
+            if let Some(count) = self.bigrams.get(&(p1, word_id)) {
+                let history = self.unigrams[p1 as usize].max(*count);
+                return (BACKOFF * *count as f32 / history as f32).log10()
+            }
+        }
+
+        // unigram
+        let count = self.unigrams[word_id as usize];
+        if count == 0 {
+            return UNKNOWN_LOG_PROB
+        }
+        let backoff = match prev1 {
+            Some(_) if prev2.is_some() => BACKOFF * BACKOFF,
+            Some(_) => BACKOFF,
+            None => 1.0,
+        };
+        (backoff * count as f32 / self.total as f32).log10()
+    }
+
+    fn index_followers(&mut self) {
+        self.followers.clear();
+        self.pair_followers.clear();
+        for (w1, w2) in self.bigrams.keys() {
+            self.followers.entry(*w1).or_default().push(*w2);
+        }
+        for (w1, w2, w3) in self.trigrams.keys() {
+            self.pair_followers.entry((*w1, *w2)).or_default().push(*w3);
+        }
+    }
+
+    fn id(&self, word: &str) -> Option<u32> {
+        self.index.get(&word.to_lowercase()).copied()
+    }
+
+    fn add_word(&mut self, word: &str) -> u32 {
+        if let Some(id) = self.index.get(word) {
+            return *id
+        }
+        let id = self.vocab.len() as u32;
+        self.vocab.push(word.to_string());
+        self.index.insert(word.to_string(), id);
+        self.unigrams.push(0);
+        id
+    }
+
+    /// File layout, all integers little endian:
+    /// magic, version, vocab count, [word length (u16), word bytes, count],
+    /// bigram count, [w1, w2, count], trigram count, [w1, w2, w3, count]
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        write_u32(writer, VERSION)?;
+
+        write_u32(writer, self.vocab.len() as u32)?;
+        for (word, count) in self.vocab.iter().zip(&self.unigrams) {
+            writer.write_all(&(word.len() as u16).to_le_bytes())?;
+            writer.write_all(word.as_bytes())?;
+            write_u32(writer, *count)?;
+        }
+
+        write_u32(writer, self.bigrams.len() as u32)?;
+        for ((w1, w2), count) in &self.bigrams {
+            for value in [*w1, *w2, *count] {
+                write_u32(writer, value)?;
+            }
+        }
+
+        write_u32(writer, self.trigrams.len() as u32)?;
+        for ((w1, w2, w3), count) in &self.trigrams {
+            for value in [*w1, *w2, *w3, *count] {
+                write_u32(writer, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a surfboard language model"))
+        }
+        let version = read_u32(reader)?;
+        if version != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported language model version {version}")))
+        }
+
+        let mut model = NgramModel::default();
+        let vocab_len = read_u32(reader)?;
+        for _ in 0..vocab_len {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            let mut bytes = vec![0; u16::from_le_bytes(len) as usize];
+            reader.read_exact(&mut bytes)?;
+            let word = String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let id = model.add_word(&word);
+            let count = read_u32(reader)?;
+            model.unigrams[id as usize] = count;
+            model.total += count as u64;
+        }
+
+        let bigram_len = read_u32(reader)?;
+        for _ in 0..bigram_len {
+            let key = (read_u32(reader)?, read_u32(reader)?);
+            model.bigrams.insert(key, read_u32(reader)?);
+        }
+
+        let trigram_len = read_u32(reader)?;
+        for _ in 0..trigram_len {
+            let key = (read_u32(reader)?, read_u32(reader)?, read_u32(reader)?);
+            model.trigrams.insert(key, read_u32(reader)?);
+        }
+
+        // guard against ids outside of the vocab
+        let vocab_len = model.vocab.len() as u32;
+        if model.bigrams.keys().any(|(a, b)| *a >= vocab_len || *b >= vocab_len)
+            || model.trigrams.keys().any(|(a, b, c)| *a >= vocab_len || *b >= vocab_len || *c >= vocab_len) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "language model references unknown words"))
+        }
+        model.index_followers();
+        Ok(model)
+    }
+}
+
+
+/// Splits text into sentences of lowercase words, in the same composed form as the dictionary.
+/// Words keep inner apostrophes so contractions like "don't" stay whole.
+pub fn tokenize(text: &str) -> Vec<Vec<String>> {
+    let mut sentences = Vec::new();
+    let mut sentence = Vec::new();
+    let mut word = String::new();
+
+    for c in unicode::normalize(text).chars() {
+        if c.is_alphanumeric() || (c == '\'' && !word.is_empty()) {
+            word.extend(c.to_lowercase());
+            continue
+        }
+
+        push_word(&mut sentence, &mut word);
+        if matches!(c, '.' | '!' | '?' | '\n') && !sentence.is_empty() {
+            sentences.push(std::mem::take(&mut sentence));
+        }
+    }
+
+    push_word(&mut sentence, &mut word);
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+    sentences
+}
+
+fn push_word(sentence: &mut Vec<String>, word: &mut String) {
+    let trimmed = word.trim_end_matches('\'');
+    if !trimmed.is_empty() {
+        sentence.push(trimmed.to_string());
+    }
+    word.clear();
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CORPUS: &str = "The cat sat. The cat ran. The dog sat.";
+
+    fn context(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    fn assert_log_prob(model: &NgramModel, words: &[&str], word: &str, probability: f32) {
+        let log_prob = model.log_prob(&context(words), word);
+        assert!((log_prob - probability.log10()).abs() < 1e-5, "{words:?} {word}: {log_prob} != log10 {probability}");
+    }
+
+    #[test]
+    fn round_trip() {
+        let model = NgramModel::from_corpus(CORPUS, 1);
+        let mut bytes = Vec::new();
+        model.write_to(&mut bytes).unwrap();
+        let read = NgramModel::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(read.vocab_len(), 5);
+        assert_eq!(read.bigram_len(), model.bigram_len());
+        assert_eq!(read.trigram_len(), model.trigram_len());
+        let contexts: [&[&str]; 4] = [&[], &["the"], &["the", "cat"], &["cat", "dog"]];
+        for words in contexts {
+            for word in ["the", "cat", "sat", "ran", "dog", "bird"] {
+                assert_eq!(read.log_prob(&context(words), word), model.log_prob(&context(words), word));
+            }
+            assert_eq!(read.predict(&context(words), 3), model.predict(&context(words), 3));
+        }
+    }
+
+    #[test]
+    fn stupid_backoff() {
+        let model = NgramModel::from_corpus(CORPUS, 1);
+        // "the cat" is seen twice, once followed by "sat"
+        assert_log_prob(&model, &["the", "cat"], "sat", 0.5);
+        // no "cat dog sat", so back off to "dog sat", seen every time "dog" is
+        assert_log_prob(&model, &["cat", "dog"], "sat", BACKOFF);
+        // "sat the" crosses a sentence, so back off to the unigram, 3 of the 9 words
+        assert_log_prob(&model, &["sat"], "the", BACKOFF * 3.0 / 9.0);
+        assert_log_prob(&model, &["cat", "sat"], "dog", BACKOFF * BACKOFF / 9.0);
+        assert_log_prob(&model, &[], "the", 3.0 / 9.0);
+        assert_eq!(model.log_prob(&context(&["the"]), "bird"), UNKNOWN_LOG_PROB);
+    }
+
+    #[test]
+    fn rejects_a_bad_magic_or_version() {
+        let mut bytes = Vec::new();
+        NgramModel::from_corpus(CORPUS, 1).write_to(&mut bytes).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[..4].copy_from_slice(b"XXXX");
+        let err = NgramModel::read_from(&mut bad_magic.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut bad_version = bytes.clone();
+        bad_version[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let err = NgramModel::read_from(&mut bad_version.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
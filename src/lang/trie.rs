@@ -0,0 +1,159 @@
+use super::*;
+
+/// A dictionary stored as a tree of characters, so a word can be followed one letter at a time.
+/// Words are filed under their folded form, lowercase and without accents, so that the keys
+/// they are typed with lead to them, and each node keeps the words that fold to it, eg. "resume" and "résumé".
+/// Nodes live in one vec and refer to each other by index, the root is node 0.
+#[derive(Debug, Clone)]
+pub struct WordTrie {
+    nodes: Vec<TrieNode>,
+    elisions: Vec<(u32, Elision)>, // node reached by the folded prefix, and the elision
+    len: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: Vec<(char, u32)>, // sorted by char
+    words: Vec<String>, // NFC words that fold to this node
+}
+
+impl Default for WordTrie {
+    fn default() -> Self {
+        WordTrie {
+            nodes: vec![TrieNode::default()],
+            elisions: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl WordTrie {
+    pub const ROOT: u32 = 0;
+
+    pub fn new() -> Self {
+        WordTrie::default()
+    }
+
+    /// Adds the word, returns false if it was already there.
+    pub fn insert(&mut self, word: &str) -> bool {
+        let word = unicode::normalize(word);
+        let node = self.add_path(&unicode::fold(&word));
+        let words = &mut self.nodes[node as usize].words;
+        if words.contains(&word) {
+            return false
+        }
+        words.push(word);
+        self.len += 1;
+        true
+    }
+
+    /// Follows the folded letters from the root, adding any nodes that are missing.
+    fn add_path(&mut self, letters: &str) -> u32 {
+        let mut node = Self::ROOT;
+        for c in letters.chars() {
+            node = match self.child(node, c) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len() as u32;
+                    self.nodes.push(TrieNode::default());
+                    let children = &mut self.nodes[node as usize].children;
+                    let index = children.partition_point(|(label, _)| *label < c);
+                    children.insert(index, (c, child));
+                    child
+                }
+            };
+        }
+        node
+    }
+
+    /// Adds a prefix that joins onto words, so they can be followed from the root again after it.
+    pub fn add_elision(&mut self, elision: Elision) {
+        let node = self.add_path(&unicode::fold(&elision.prefix));
+        self.elisions.push((node, elision));
+    }
+
+    /// Index of the elisions whose prefix ends at the node.
+    pub fn elisions_at(&self, node: u32) -> impl Iterator<Item = usize> + '_ {
+        self.elisions
+            .iter()
+            .enumerate()
+            .filter(move |(_, (elision_node, _))| *elision_node == node)
+            .map(|(index, _)| index)
+    }
+
+    /// The words that end at the node, after the elision if there is one, as they are written.
+    pub fn words_after(&self, node: u32, elision: Option<usize>) -> impl Iterator<Item = String> + '_ {
+        let elision = elision.map(|index| &self.elisions[index].1);
+        self.words(node)
+            .iter()
+            .filter(move |word| elision.is_none_or(|elision| elision.allows(word)))
+            .map(move |word| match elision {
+                Some(elision) => format!("{}{word}", elision.prefix),
+                None => word.clone(),
+            })
+    }
+
+    /// Removes the word, returns false if it wasn't there.
+    /// The nodes are kept, as they are likely shared with other words.
+    pub fn remove(&mut self, word: &str) -> bool {
+        let word = unicode::normalize(word);
+        let Some(node) = self.find(&word) else {
+            return false
+        };
+        let words = &mut self.nodes[node as usize].words;
+        let Some(index) = words.iter().position(|item| *item == word) else {
+            return false
+        };
+        words.remove(index);
+        self.len -= 1;
+        true
+    }
+
+    /// True if the word is in the dictionary exactly as written, accents included.
+    pub fn contains(&self, word: &str) -> bool {
+        let word = unicode::normalize(word);
+        self.find(&word).is_some_and(|node| self.words(node).contains(&word))
+    }
+
+    /// The node reached by following the folded prefix from the root.
+    pub fn find(&self, prefix: &str) -> Option<u32> {
+        self.follow(Self::ROOT, &unicode::fold(prefix))
+    }
+
+    /// The node reached by following the folded letters from the node.
+    pub fn follow(&self, node: u32, letters: &str) -> Option<u32> {
+        letters.chars().try_fold(node, |node, c| self.child(node, c))
+    }
+
+    pub fn child(&self, node: u32, c: char) -> Option<u32> {
+        let children = &self.nodes[node as usize].children;
+        children
+            .binary_search_by(|(label, _)| label.cmp(&c))
+            .ok()
+            .map(|index| children[index].1)
+    }
+
+    /// The letters that follow the node, with the node each leads to.
+    pub fn children(&self, node: u32) -> impl Iterator<Item = (char, u32)> + '_ {
+        self.nodes[node as usize].children.iter().copied()
+    }
+
+    /// True if a word ends at the node.
+    pub fn is_word(&self, node: u32) -> bool {
+        !self.nodes[node as usize].words.is_empty()
+    }
+
+    /// The words that end at the node, as they are written.
+    pub fn words(&self, node: u32) -> &[String] {
+        &self.nodes[node as usize].words
+    }
+
+    /// Number of words.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
@@ -0,0 +1,61 @@
+use unicode_normalization::{
+    char::is_combining_mark,
+    UnicodeNormalization
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Letters that don't decompose into a base letter and a mark, with what they fold to.
+static FOLDS: &[(char, &str)] = &[
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('œ', "oe"),
+    ('ø', "o"),
+    ('đ', "d"),
+    ('ð', "d"),
+    ('ħ', "h"),
+    ('ı', "i"),
+    ('ł', "l"),
+    ('þ', "th"),
+];
+
+/// Normalises text to NFC, the composed form that words are stored and typed in,
+/// so the same word always has the same chars whichever form it was written in.
+pub fn normalize(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// Folds text for matching keys to words: lowercase, without accents, marks or punctuation,
+/// so a glide over "e" matches words with "é", "è" or "ê" in them, and "dont" matches "don't".
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    let letters = text
+        .nfd()
+        .filter(|c| !is_combining_mark(*c) && c.is_alphanumeric())
+        .flat_map(char::to_lowercase);
+    for c in letters {
+        match FOLDS.iter().find(|(letter, _)| *letter == c) {
+            Some((_, replacement)) => folded.push_str(replacement),
+            None => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// Splits text into grapheme clusters, what the user sees as one character,
+/// eg. a letter with a combining accent, or a Devanagari conjunct.
+pub fn graphemes(text: &str) -> impl Iterator<Item = &str> {
+    text.graphemes(true)
+}
+
+/// The folded letters of a key, None if the key isn't a single letter and so can't be part of a word.
+/// A letter can be more than one char, when it has marks or is written as a cluster.
+pub fn key_letters(label: &str) -> Option<String> {
+    let mut clusters = graphemes(label);
+    let (Some(cluster), None) = (clusters.next(), clusters.next()) else {
+        return None
+    };
+    if !cluster.chars().next().is_some_and(char::is_alphabetic) {
+        return None
+    }
+    Some(fold(cluster)).filter(|letters| !letters.is_empty())
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{
+    Path,
+    PathBuf
+};
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH
+};
+use crate::utils::*;
+
+static LEARN_COUNT: u32 = 2; // times a word must be typed before it is learned
+
+/// A word the user has typed that is not in the dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearnedWord {
+    pub word: String,
+    pub count: u32,
+    pub first_used: u64, // unix seconds
+    pub last_used: u64, // unix seconds
+}
+
+impl LearnedWord {
+    /// Words are only added to the dictionary once they have been used enough.
+    pub fn is_learned(&self) -> bool {
+        self.count >= LEARN_COUNT
+    }
+}
+
+/// Words the user types that the dictionary doesn't know, such as names and jargon.
+/// Stored under $XDG_DATA_HOME/surfboard as tab separated lines of: word, count, first used, last used.
+#[derive(Debug, Clone, Default)]
+pub struct UserDictionary {
+    path: Option<PathBuf>,
+    words: BTreeMap<String, LearnedWord>,
+}
+
+impl UserDictionary {
+    /// Loads the user dictionary from the xdg data dir, empty if it doesn't exist yet.
+    pub fn load() -> Self {
+        let path = match xdg::BaseDirectories::with_prefix("surfboard").map(|dirs| dirs.place_data_file(globals::USER_DICTIONARY_FILE)) {
+            Ok(Ok(path)) => path,
+            _ => {
+                error!("Unable to find the xdg data dir, learned words will not be saved");
+                return UserDictionary::default()
+            }
+        };
+
+        if !path.exists() {
+            return UserDictionary {
+                path: Some(path),
+                words: BTreeMap::new(),
+            }
+        }
+
+        match Self::load_from(&path) {
+            Ok(dictionary) => dictionary,
+            Err(err) => {
+                error!("Failed to load user dictionary \"{}\", with error: {}", path.display(), err);
+                UserDictionary {
+                    path: Some(path),
+                    words: BTreeMap::new(),
+                }
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut words = BTreeMap::new();
+        for line in contents.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [word, count, first_used, last_used] = fields[..] else {
+                warn!("Skipping malformed user dictionary line: {:?}", line);
+                continue
+            };
+            let (Ok(count), Ok(first_used), Ok(last_used)) = (count.parse(), first_used.parse(), last_used.parse()) else {
+                warn!("Skipping malformed user dictionary line: {:?}", line);
+                continue
+            };
+            words.insert(word.to_string(), LearnedWord {
+                word: word.to_string(),
+                count,
+                first_used,
+                last_used,
+            });
+        }
+
+        Ok(UserDictionary {
+            path: Some(path.to_path_buf()),
+            words,
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(())
+        };
+        let mut contents = String::from("# word\tcount\tfirst used\tlast used\n");
+        for item in self.words.values() {
+            contents.push_str(&format!("{}\t{}\t{}\t{}\n", item.word, item.count, item.first_used, item.last_used));
+        }
+        std::fs::write(path, contents)
+    }
+
+    /// Counts a use of the word. Picking a word from the suggestions learns it straight away.
+    /// Returns true if the word has just become learned.
+    pub fn learn(&mut self, word: &str, picked: bool) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+        let item = self.words.entry(word.to_string()).or_insert(LearnedWord {
+            word: word.to_string(),
+            count: 0,
+            first_used: now,
+            last_used: now,
+        });
+
+        let was_learned = item.is_learned();
+        item.count += 1;
+        if picked {
+            item.count = item.count.max(LEARN_COUNT);
+        }
+        item.last_used = now;
+        !was_learned && item.is_learned()
+    }
+
+    /// Removes the word, returns true if it was known.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.words.remove(word).is_some()
+    }
+
+    /// Words that have been used enough to be added to the dictionary.
+    pub fn learned(&self) -> impl Iterator<Item = &LearnedWord> {
+        self.words.values().filter(|item| item.is_learned())
+    }
+}
@@ -15,6 +15,8 @@ mod macros;
 
 mod app;
 mod comp;
+mod lang;
+mod tools;
 mod utils;
 mod views;
 use app::*;
@@ -24,6 +26,9 @@ use utils::*;
 pub fn main() {
     functions::init_env_var();
     functions::init_logger();
+    if tools::run() {
+        return
+    }
     info!("== Start Surfboard ==");
     start();
 }
@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::app::*;
+
+/// How well the search ranked a set of recorded glides.
+#[derive(Debug, Clone, Default)]
+pub struct BenchResult {
+    pub count: usize,
+    pub top_1: usize,
+    pub top_3: usize,
+    pub latencies: Vec<Duration>, // sorted, one per glide
+}
+
+impl BenchResult {
+    pub fn top_1_percent(&self) -> f32 {
+        self.top_1 as f32 * 100.0 / self.count.max(1) as f32
+    }
+
+    pub fn top_3_percent(&self) -> f32 {
+        self.top_3 as f32 * 100.0 / self.count.max(1) as f32
+    }
+}
+
+/// Replays recorded glides through the search and reports how often the intended word is ranked first or in the top 3.
+/// usage: surfboard bench [dir]
+/// The dir defaults to the recordings in the xdg data dir, record glides from the settings.
+pub fn bench(args: &[String]) {
+    let Some(recordings) = load_bench_recordings(args.first()) else {
+        return
+    };
+
+    let mut search_handler = SearchHandler::new();
+    search_handler.load_dictionary();
+
+    // the search logs every step, which would bury the results
+    log::set_max_level(log::LevelFilter::Warn);
+    let result = replay(&mut search_handler, &recordings, true);
+    log::set_max_level(log::LevelFilter::Info);
+
+    let count = result.count;
+    let mean = result.latencies.iter().sum::<Duration>() / count as u32;
+    let p95 = result.latencies[(count * 95 / 100).min(count - 1)];
+    info!("glides: {}", count);
+    info!("top-1: {:.1}% ({})", result.top_1_percent(), result.top_1);
+    info!("top-3: {:.1}% ({})", result.top_3_percent(), result.top_3);
+    info!("latency: mean {:.2}ms, p95 {:.2}ms, max {:.2}ms",
+        mean.as_secs_f32() * 1000.0, p95.as_secs_f32() * 1000.0, result.latencies[count - 1].as_secs_f32() * 1000.0);
+}
+
+/// Loads the labelled recordings from the dir, or the xdg data dir when none is given.
+/// Unlabelled glides can't be scored, they are counted and skipped until a word line is added to them.
+/// Logs the reason and returns None if there is nothing to replay.
+pub fn load_bench_recordings(dir: Option<&String>) -> Option<Vec<(PathBuf, GlideRecording)>> {
+    let dir = match dir {
+        Some(dir) => PathBuf::from(dir),
+        None => match recording_dir() {
+            Some(dir) => dir,
+            None => {
+                error!("Unable to find the xdg data dir, pass a recordings dir instead");
+                return None
+            }
+        },
+    };
+
+    let recordings = load_recordings(&dir).map(|recordings| {
+        let count = recordings.len();
+        let labelled: Vec<(PathBuf, GlideRecording)> = recordings.into_iter().filter(|(_, recording)| recording.is_labelled()).collect();
+        if labelled.len() < count {
+            warn!("Skipping {} unlabelled glides in {}", count - labelled.len(), dir.display());
+        }
+        labelled
+    });
+    match recordings {
+        Ok(recordings) if recordings.is_empty() => {
+            error!("No labelled glide recordings found in {}", dir.display());
+            None
+        }
+        Ok(recordings) => Some(recordings),
+        Err(err) => {
+            error!("Failed to read recordings \"{}\", with error: {}", dir.display(), err);
+            None
+        }
+    }
+}
+
+/// Runs each recording through the search, logging the misses when log_misses is true.
+pub fn replay(search_handler: &mut SearchHandler, recordings: &[(PathBuf, GlideRecording)], log_misses: bool) -> BenchResult {
+    let mut result = BenchResult::default();
+
+    for (path, recording) in recordings.iter() {
+        // lay out the keys the glide was recorded on
+        let _ = search_handler.update(search::Message::Reset);
+        for (label, bounds) in recording.keys.iter() {
+            let _ = search_handler.update(search::Message::Update(label.clone(), *bounds));
+        }
+        search_handler.set_context(&recording.context);
+
+        // replay the glide at the speed it was recorded, so the timing counts
+        let now = std::time::Instant::now();
+        let _ = search_handler.start();
+        for (time, point) in recording.points.iter() {
+            let _ = search_handler.update_move_at(*point, now + Duration::from_millis(*time as u64));
+        }
+        search_handler.finish();
+        result.latencies.push(now.elapsed());
+
+        let words: Vec<&str> = search_handler.suggestions().iter().map(|suggestion| suggestion.text.as_str()).collect();
+        match words.iter().position(|word| *word == recording.word) {
+            Some(0) => {
+                result.top_1 += 1;
+                result.top_3 += 1;
+            }
+            Some(rank) if rank < 3 => result.top_3 += 1,
+            _ if log_misses => warn!("miss: {} -> {:?} ({})", recording.word, words, path.display()),
+            _ => {}
+        }
+    }
+
+    result.count = recordings.len();
+    result.latencies.sort();
+    result
+}
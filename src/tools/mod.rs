@@ -0,0 +1,26 @@
+pub mod ngram;
+pub mod bench;
+pub mod synth;
+pub mod tune;
+
+// re-export
+pub use super::tools::ngram::*;
+pub use super::tools::bench::*;
+pub use super::tools::synth::*;
+pub use super::tools::tune::*;
+
+
+/// Runs a command line tool when one is named as the first argument.
+/// Returns true if a tool ran, in which case the keyboard should not start.
+pub fn run() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    let tool_args = args.get(2..).unwrap_or_default();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("build-ngram") => build_ngram(tool_args),
+        Some("bench") => bench(tool_args),
+        Some("synth-check") => synth_check(tool_args),
+        Some("tune") => tune(tool_args),
+        _ => return false,
+    }
+    true
+}
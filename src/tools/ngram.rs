@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use crate::lang::*;
+use crate::utils::*;
+
+static DEFAULT_MIN_COUNT: u32 = 2;
+
+/// Builds a language model from a plain text corpus.  
+/// usage: surfboard build-ngram <corpus.txt> [output] [min_count]  
+/// The output defaults to the xdg data dir, where the keyboard looks for it.
+pub fn build_ngram(args: &[String]) {
+    let Some(corpus_path) = args.first() else {
+        error!("usage: surfboard build-ngram <corpus.txt> [output] [min_count]");
+        return
+    };
+
+    let output = match args.get(1) {
+        Some(path) => PathBuf::from(path),
+        None => match xdg::BaseDirectories::with_prefix("surfboard").map(|dirs| dirs.place_data_file(globals::NGRAM_FILE)) {
+            Ok(Ok(path)) => path,
+            _ => {
+                error!("Unable to find the xdg data dir, pass an output path instead");
+                return
+            }
+        },
+    };
+
+    let min_count = match args.get(2).map(|arg| arg.parse::<u32>()) {
+        Some(Ok(count)) => count,
+        Some(Err(_)) => {
+            error!("min_count must be a number");
+            return
+        }
+        None => DEFAULT_MIN_COUNT,
+    };
+
+    let corpus = match std::fs::read_to_string(corpus_path) {
+        Ok(corpus) => corpus,
+        Err(err) => {
+            error!("Failed to read corpus \"{}\", with error: {}", corpus_path, err);
+            return
+        }
+    };
+
+    let now = std::time::Instant::now();
+    let model = NgramModel::from_corpus(&corpus, min_count);
+    info!("Built model in {}ms: {} words, {} bigrams, {} trigrams",
+        now.elapsed().as_millis(), model.vocab_len(), model.bigram_len(), model.trigram_len());
+
+    match model.save(&output) {
+        Ok(_) => info!("Saved language model to {}", output.display()),
+        Err(err) => error!("Failed to save language model \"{}\", with error: {}", output.display(), err),
+    }
+}
@@ -0,0 +1,359 @@
+use iced::{
+    mouse,
+    Event,
+    Point,
+    Rectangle,
+    Size
+};
+use crate::app::*;
+use crate::lang::unicode;
+use crate::utils::*;
+use crate::views::compact_qwerty;
+
+static SAMPLE_INTERVAL: u32 = 8; // ms between points, a 125hz touch screen
+static SPEED: f32 = 1.2; // average pixels per ms
+static DEFAULT_COUNT: usize = 1000;
+static DEFAULT_SEED: u64 = 1;
+
+/// A small, seeded random number generator, so generated glides can be reproduced.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    /// xorshift64*, uniform in 0..1
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545F4914F6CDD1D) >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// Normally distributed with a mean of 0, using the Box-Muller transform.
+    pub fn gaussian(&mut self, sigma: f32) -> f32 {
+        let u1 = self.next_f32().max(f32::EPSILON);
+        let u2 = self.next_f32();
+        sigma * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+    }
+}
+
+/// Generates gestures that look like a person glided a word:
+/// each key is aimed at loosely, corners are cut, the end can overshoot,
+/// the finger slows down at each key, pauses on double letters, and the touch points jitter.
+#[derive(Debug, Clone)]
+pub struct GlideSynth {
+    pub keys: Vec<(String, Rectangle)>,
+    pub aim: f32, // spread of the aim around key centers, as a share of the key size
+    pub corner_cut: f32, // how far corners are pulled toward the straight line, 0-1
+    pub overshoot: f32, // most pixels the end can overshoot the last key
+    pub jitter: f32, // pixels of noise on each point
+    pub double_pause: u32, // ms the finger rests on a double letter
+    rng: Rng,
+}
+
+impl GlideSynth {
+    pub fn new(keys: Vec<(String, Rectangle)>, seed: u64) -> Self {
+        GlideSynth {
+            keys,
+            aim: 0.15,
+            corner_cut: 0.25,
+            overshoot: 12.0,
+            jitter: 1.5,
+            double_pause: 180,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Timestamped points for the word, or None if a letter has no key.
+    pub fn generate(&mut self, word: &str) -> Option<Vec<(u32, Point)>> {
+        // aim somewhere near the center of each key, double letters stay on the same key
+        let mut anchors: Vec<Point> = Vec::new();
+        let mut pauses: Vec<u32> = Vec::new(); // ms to rest on each anchor
+        let mut prev_char = None;
+        for c in unicode::fold(word).chars() {
+            if prev_char == Some(c) {
+                if let Some(pause) = pauses.last_mut() {
+                    *pause = self.double_pause + self.rng.range(0.0, 80.0) as u32;
+                }
+                continue
+            }
+            prev_char = Some(c);
+            let letter = c.to_string();
+            let (_, bounds) = self.keys.iter().find(|(key, _)| unicode::key_letters(key).as_ref() == Some(&letter))?;
+            let center = bounds.center();
+            anchors.push(Point::new(
+                center.x + self.rng.gaussian(bounds.width * self.aim),
+                center.y + self.rng.gaussian(bounds.height * self.aim),
+            ));
+            pauses.push(0);
+        }
+
+        // cut corners, pulling each middle anchor toward its neighbours
+        let mut path = anchors.clone();
+        for i in 1..anchors.len().saturating_sub(1) {
+            let midpoint = Point::new((anchors[i - 1].x + anchors[i + 1].x) / 2.0, (anchors[i - 1].y + anchors[i + 1].y) / 2.0);
+            let cut = self.rng.range(0.0, self.corner_cut);
+            path[i] = Point::new(
+                anchors[i].x + (midpoint.x - anchors[i].x) * cut,
+                anchors[i].y + (midpoint.y - anchors[i].y) * cut,
+            );
+        }
+
+        // overshoot the last key along the final direction
+        if path.len() > 1 {
+            let last = path[path.len() - 1];
+            let prev = path[path.len() - 2];
+            let direction = functions::normalize_point(Point::new(last.x - prev.x, last.y - prev.y));
+            let overshoot = self.rng.range(0.0, self.overshoot);
+            path.push(Point::new(last.x + direction.x * overshoot, last.y + direction.y * overshoot));
+        }
+
+        // a single key glide still needs some movement
+        if path.len() == 1 {
+            let start = path[0];
+            path.push(Point::new(start.x + self.rng.gaussian(4.0), start.y + self.rng.gaussian(4.0)));
+        }
+
+        // walk each segment, easing in and out so the finger is slowest at the keys
+        let speed = SPEED * self.rng.range(0.7, 1.3);
+        let mut points = vec![(0, path[0])];
+        let mut time = 0;
+        for (index, segment) in path.windows(2).enumerate() {
+            let (start, end) = (segment[0], segment[1]);
+
+            // rest on the key before moving on
+            let pause = pauses.get(index).copied().unwrap_or_default();
+            for _ in 0..pause / SAMPLE_INTERVAL {
+                time += SAMPLE_INTERVAL;
+                points.push((time, Point::new(
+                    start.x + self.rng.gaussian(self.jitter),
+                    start.y + self.rng.gaussian(self.jitter),
+                )));
+            }
+
+            let duration = (start.distance(end) / speed).max(SAMPLE_INTERVAL as f32);
+            let steps = (duration / SAMPLE_INTERVAL as f32).ceil() as u32;
+            for step in 1..=steps {
+                let t = step as f32 / steps as f32;
+                let eased = t * t * (3.0 - 2.0 * t);
+                time += SAMPLE_INTERVAL;
+                points.push((time, Point::new(
+                    start.x + (end.x - start.x) * eased + self.rng.gaussian(self.jitter),
+                    start.y + (end.y - start.y) * eased + self.rng.gaussian(self.jitter),
+                )));
+            }
+        }
+        Some(points)
+    }
+}
+
+
+/// Key bounds of the compact qwerty layout in a window of the given size, below the suggestion bar.
+/// Rows share the height and keys share the width of their row, as the layout fills its space.
+/// Only the keys that report their bounds to the search are returned, with the label they report.
+pub fn compact_qwerty_keys(size: Size) -> Vec<(String, Rectangle)> {
+    let row_height = (size.height - SUGGESTION_HEIGHT) / compact_qwerty::ROWS.len() as f32;
+    let mut keys = Vec::new();
+    for (row, labels) in compact_qwerty::ROWS.iter().enumerate() {
+        let width = size.width / labels.len() as f32;
+        for (column, label) in labels.iter().enumerate() {
+            let Some(label) = compact_qwerty::search_label(label) else {
+                continue
+            };
+            keys.push((label.to_string(), Rectangle {
+                x: column as f32 * width,
+                y: SUGGESTION_HEIGHT + row as f32 * row_height,
+                width,
+                height: row_height,
+            }));
+        }
+    }
+    keys
+}
+
+
+/// Glides dictionary words through the input handler, as mouse events, and checks the word that gets committed.
+/// usage: surfboard synth-check [count] [seed] [min_accuracy]
+/// The most common `count` words are used. Exits with an error if fewer than min_accuracy percent are correct.
+pub fn synth_check(args: &[String]) {
+    let (Ok(count), Ok(seed), Ok(min_accuracy)) = (
+        args.first().map_or(Ok(DEFAULT_COUNT), |arg| arg.parse::<usize>()),
+        args.get(1).map_or(Ok(DEFAULT_SEED), |arg| arg.parse::<u64>()),
+        args.get(2).map_or(Ok(0.0), |arg| arg.parse::<f32>()),
+    ) else {
+        error!("usage: surfboard synth-check [count] [seed] [min_accuracy]");
+        return
+    };
+
+    let keys = compact_qwerty_keys(MainApp::window_size());
+    let mut synth = GlideSynth::new(keys.clone(), seed);
+
+    let mut search_handler = SearchHandler::new();
+    search_handler.load_dictionary();
+    for (label, bounds) in keys.iter() {
+        let _ = search_handler.update(search::Message::Update(label.clone(), *bounds));
+    }
+
+    // taps are not glides, so single letters are skipped
+    let words: Vec<&str> = globals::DICTIONARY
+        .split_whitespace()
+        .filter(|word| word.chars().count() > 1)
+        .take(count)
+        .collect();
+
+    // the search logs every step, which would bury the results
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let mut correct = 0;
+    let mut skipped = 0;
+    for word in words.iter() {
+        let Some(points) = synth.generate(word) else {
+            skipped += 1;
+            continue
+        };
+
+        // each word starts fresh, so earlier commits don't sway the ranking
+        let mut search_handler = search_handler.clone();
+        let mut input_handler = InputHandler::new();
+        let mut gesture_handler = GestureHandler::new();
+        let mut window_handler = WindowHandler::new();
+
+        let (_, first) = points[0];
+        let mut events = vec![
+            Event::Mouse(mouse::Event::CursorMoved { position: first }),
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+        ];
+        events.extend(points.iter().map(|(_, position)| Event::Mouse(mouse::Event::CursorMoved { position: *position })));
+        events.push(Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)));
+        for event in events.iter() {
+            let _ = input_handler.update_event(event, &mut gesture_handler, &mut window_handler, &mut search_handler);
+        }
+
+        match search_handler.context().last() {
+            Some(committed) if committed == word => correct += 1,
+            committed => warn!("miss: {} -> {:?}", word, committed),
+        }
+    }
+    log::set_max_level(log::LevelFilter::Info);
+
+    let checked = words.len() - skipped;
+    let accuracy = correct as f32 * 100.0 / checked.max(1) as f32;
+    info!("words: {} ({} skipped, not on the layout)", checked, skipped);
+    info!("top-1: {:.1}% ({})", accuracy, correct);
+
+    if accuracy < min_accuracy {
+        error!("Accuracy is below {:.1}%", min_accuracy);
+        std::process::exit(1);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::time::{Duration, Instant};
+    use std::sync::Once;
+
+    static SIZE: Size = Size::new(800.0, 300.0);
+
+    /// Points the xdg dirs at an empty dir, so the user's own config, dictionary and key targets don't sway the results.
+    fn isolate() {
+        static ISOLATE: Once = Once::new();
+        ISOLATE.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("surfboard-test-{}", std::process::id()));
+            let _ = std::fs::create_dir_all(&dir);
+            unsafe {
+                for var in ["XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_CONFIG_DIRS", "XDG_DATA_DIRS"] {
+                    std::env::set_var(var, &dir);
+                }
+            }
+        });
+    }
+
+    /// A search handler with the builtin dictionary, over the compact qwerty keys.
+    fn search_handler() -> SearchHandler {
+        isolate();
+        let mut search_handler = SearchHandler::new();
+        search_handler.load_dictionary();
+        for (label, bounds) in compact_qwerty_keys(SIZE) {
+            let _ = search_handler.update(search::Message::Update(label, bounds));
+        }
+        search_handler
+    }
+
+    /// Glides the word through the search, returning the top suggestion.
+    fn glide(search_handler: &SearchHandler, synth: &mut GlideSynth, word: &str) -> Option<String> {
+        let points = synth.generate(word)?;
+        let mut search_handler = search_handler.clone();
+        let now = Instant::now();
+        let _ = search_handler.start();
+        for (time, point) in points {
+            let _ = search_handler.update_move_at(point, now + Duration::from_millis(time as u64));
+        }
+        search_handler.finish();
+        search_handler.suggestions().first().map(|suggestion| suggestion.text.clone())
+    }
+
+    #[test]
+    fn every_letter_has_a_key() {
+        let keys = compact_qwerty_keys(SIZE);
+        for letter in 'a'..='z' {
+            assert!(keys.iter().any(|(label, _)| *label == letter.to_string()), "no key for {letter}");
+        }
+    }
+
+    #[test]
+    fn keys_are_below_the_suggestions() {
+        for (label, bounds) in compact_qwerty_keys(SIZE) {
+            assert!(bounds.y >= SUGGESTION_HEIGHT, "{label} overlaps the suggestion bar");
+            assert!(bounds.x + bounds.width <= SIZE.width + 0.01, "{label} is outside the window");
+        }
+    }
+
+    #[test]
+    fn clean_glides_rank_the_word_first() {
+        let search_handler = search_handler();
+        let mut synth = GlideSynth::new(compact_qwerty_keys(SIZE), DEFAULT_SEED);
+        synth.aim = 0.0;
+        synth.corner_cut = 0.0;
+        synth.overshoot = 0.0;
+        synth.jitter = 0.0;
+
+        for word in ["hello", "world", "keyboard", "people", "think", "because", "question", "little", "about"] {
+            assert_eq!(glide(&search_handler, &mut synth, word).as_deref(), Some(word));
+        }
+    }
+
+    #[test]
+    fn noisy_glides_mostly_rank_the_word_first() {
+        let search_handler = search_handler();
+        let mut synth = GlideSynth::new(compact_qwerty_keys(SIZE), DEFAULT_SEED);
+
+        // a guard against regressions, not a target, `surfboard synth-check` reports the real accuracy
+        let words: Vec<&str> = globals::DICTIONARY
+            .split_whitespace()
+            .filter(|word| word.chars().count() > 1)
+            .take(100)
+            .collect();
+        let correct = words.iter()
+            .filter(|word| glide(&search_handler, &mut synth, word).as_deref() == Some(**word))
+            .count();
+        assert!(correct * 100 >= words.len() * 60, "only {correct} of {} words ranked first", words.len());
+    }
+
+    #[test]
+    fn generate_is_reproducible() {
+        let keys = compact_qwerty_keys(SIZE);
+        let first = GlideSynth::new(keys.clone(), 7).generate("keyboard");
+        let second = GlideSynth::new(keys, 7).generate("keyboard");
+        assert!(first.is_some());
+        assert_eq!(first, second);
+    }
+}
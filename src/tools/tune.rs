@@ -0,0 +1,100 @@
+use crate::app::*;
+use crate::utils::*;
+use crate::utils::config::SearchParams;
+use super::*;
+
+static STEPS: [f32; 6] = [0.5, 0.7, 0.85, 1.15, 1.4, 2.0]; // multipliers tried for each param
+static MAX_ROUNDS: usize = 6;
+static DEFAULT_PROFILE: &str = "tuned";
+
+/// The tuned params by name, with how to scale each one by a step.
+static PARAMS: [(&str, fn(&mut SearchParams, f32)); 12] = [
+    ("sigma", |params, step| params.sigma *= step),
+    ("edge_weight", |params, step| scale_whole(&mut params.edge_weight, step)),
+    ("min_weight", |params, step| scale_whole(&mut params.min_weight, step)),
+    ("min_distance", |params, step| params.min_distance *= step),
+    ("language_model_weight", |params, step| params.language_model_weight *= step),
+    ("dwell_weight", |params, step| params.dwell_weight *= step),
+    ("max_dwell", |params, step| scale_whole(&mut params.max_dwell, step)),
+    ("slow_speed", |params, step| params.slow_speed *= step),
+    ("slow_weight", |params, step| scale_whole(&mut params.slow_weight, step)),
+    ("double_dwell", |params, step| scale_whole(&mut params.double_dwell, step)),
+    ("double_weight", |params, step| scale_whole(&mut params.double_weight, step)),
+    ("double_turning", |params, step| params.double_turning *= step),
+];
+
+/// Searches for the search params that rank the recorded glides best, and saves them as a profile.
+/// usage: surfboard tune [dir] [profile]
+/// Each param is scaled up and down in turn, keeping any change that helps, until nothing does.
+/// The profile is written to the xdg config dir and selected in the config.
+pub fn tune(args: &[String]) {
+    let Some(recordings) = load_bench_recordings(args.first()) else {
+        return
+    };
+    let profile = args.get(1).map_or(DEFAULT_PROFILE, |name| name.as_str());
+
+    let mut search_handler = SearchHandler::new();
+    search_handler.load_dictionary();
+
+    // the search logs every step, which would bury the results
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let mut best_params = config::get().search.clone();
+    let mut best_score = score(&mut search_handler, &recordings, &best_params);
+    let start_score = best_score;
+
+    for round in 1..=MAX_ROUNDS {
+        let mut improved: Vec<&str> = Vec::new();
+        for (name, scale) in PARAMS.iter() {
+            for step in STEPS {
+                let mut params = best_params.clone();
+                scale(&mut params, step);
+                if params == best_params {
+                    continue
+                }
+                let score = score(&mut search_handler, &recordings, &params);
+                if score > best_score {
+                    best_score = score;
+                    best_params = params;
+                    if !improved.contains(name) {
+                        improved.push(*name);
+                    }
+                }
+            }
+        }
+        // info is muted while replaying, so progress is logged as a warning
+        warn!("round {}: top-1 {:.1}%, changed {:?}, {:?}", round, best_score.0, improved, best_params);
+        if improved.is_empty() {
+            break
+        }
+    }
+    log::set_max_level(log::LevelFilter::Info);
+
+    info!("glides: {}", recordings.len());
+    info!("top-1: {:.1}% -> {:.1}%", start_score.0, best_score.0);
+    info!("top-3: {:.1}% -> {:.1}%", start_score.1, best_score.1);
+
+    match best_params.save_profile(profile) {
+        Ok(path) => info!("Saved search profile to {}", path.display()),
+        Err(err) => {
+            error!("Failed to save search profile \"{}\", with error: {}", profile, err);
+            return
+        }
+    }
+    config::update(|config| {
+        config.search_profile = Some(profile.to_string());
+        config.search = best_params;
+    });
+}
+
+/// Top-1 then top-3 accuracy, compared in that order.
+fn score(search_handler: &mut SearchHandler, recordings: &[(std::path::PathBuf, GlideRecording)], params: &SearchParams) -> (f32, f32) {
+    search_handler.set_params(params.clone());
+    let result = replay(search_handler, recordings, false);
+    (result.top_1_percent(), result.top_3_percent())
+}
+
+/// Scales a whole number param, rounding it.
+fn scale_whole(value: &mut u32, step: f32) {
+    *value = (*value as f32 * step).round() as u32;
+}
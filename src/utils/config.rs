@@ -0,0 +1,582 @@
+use pretty_ini::{
+    ini,
+    ini_file,
+    variable::Variable
+};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{
+    LazyLock,
+    RwLock,
+    RwLockReadGuard
+};
+use super::*;
+
+static PROFILE_DIR: &str = "profiles"; // search profiles, in the xdg config home
+static CONFIG: LazyLock<RwLock<Config>> = LazyLock::new(|| RwLock::new(Config::load()));
+
+/// Sections of key value pairs, as read from an ini file.
+pub type Ini = BTreeMap<String, BTreeMap<String, String>>;
+
+/// User settings, stored in $XDG_CONFIG_HOME/surfboard/surfboard.ini.
+/// Anything missing from the file keeps its default value.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub languages: Vec<String>, // enabled language tags, in the order the globe key cycles them
+    pub layout_languages: BTreeMap<String, String>, // view id -> default language tag
+    pub search_profile: Option<String>, // profile that overrides the search params, eg. from `surfboard tune`
+    pub search: SearchParams,
+    pub typing: TypingRules,
+    pub gestures: GestureActions,
+    pub theme: String, // id of the theme, see `theme::Theme`
+}
+
+/// A rule for spacing and punctuation around typed words, which can be turned off in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypingRule {
+    AutoSpace, // a space before a glided word that follows a word
+    SmartPunctuation, // tapped punctuation replaces the space before it
+    DoubleSpacePeriod, // two spaces after a word type a period
+    AutoCapitalize, // the first word of a sentence starts with a capital
+}
+
+impl TypingRule {
+    pub const ALL: [TypingRule; 4] = [
+        TypingRule::AutoSpace,
+        TypingRule::SmartPunctuation,
+        TypingRule::DoubleSpacePeriod,
+        TypingRule::AutoCapitalize,
+    ];
+
+    /// Stable name used in the config file.
+    pub fn id(&self) -> &'static str {
+        match self {
+            TypingRule::AutoSpace => "auto_space",
+            TypingRule::SmartPunctuation => "smart_punctuation",
+            TypingRule::DoubleSpacePeriod => "double_space_period",
+            TypingRule::AutoCapitalize => "auto_capitalize",
+        }
+    }
+}
+
+impl std::fmt::Display for TypingRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}",
+            match self {
+                TypingRule::AutoSpace => "auto space",
+                TypingRule::SmartPunctuation => "smart punctuation",
+                TypingRule::DoubleSpacePeriod => "double space period",
+                TypingRule::AutoCapitalize => "auto capitalize",
+            }
+        )
+    }
+}
+
+/// Which typing rules are turned on, all of them by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypingRules {
+    pub auto_space: bool,
+    pub smart_punctuation: bool,
+    pub double_space_period: bool,
+    pub auto_capitalize: bool,
+}
+
+impl Default for TypingRules {
+    fn default() -> Self {
+        TypingRules {
+            auto_space: true,
+            smart_punctuation: true,
+            double_space_period: true,
+            auto_capitalize: true,
+        }
+    }
+}
+
+impl TypingRules {
+    pub fn get(&self, rule: TypingRule) -> bool {
+        match rule {
+            TypingRule::AutoSpace => self.auto_space,
+            TypingRule::SmartPunctuation => self.smart_punctuation,
+            TypingRule::DoubleSpacePeriod => self.double_space_period,
+            TypingRule::AutoCapitalize => self.auto_capitalize,
+        }
+    }
+
+    pub fn set(&mut self, rule: TypingRule, enabled: bool) {
+        match rule {
+            TypingRule::AutoSpace => self.auto_space = enabled,
+            TypingRule::SmartPunctuation => self.smart_punctuation = enabled,
+            TypingRule::DoubleSpacePeriod => self.double_space_period = enabled,
+            TypingRule::AutoCapitalize => self.auto_capitalize = enabled,
+        }
+    }
+
+    pub fn toggle(&mut self, rule: TypingRule) {
+        self.set(rule, !self.get(rule));
+    }
+}
+
+/// What an action gesture does, written in the config as eg. `view settings`, `command foot`, `keys ctrl+c` or `hide`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GestureAction {
+    None,
+    View(String), // id of the view to change to
+    Command(String), // run with `sh -c`
+    Keys(String), // key chord, modifiers then the key, eg. "ctrl+shift+t"
+    Hide,
+}
+
+impl GestureAction {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (kind, argument) = text
+            .split_once(char::is_whitespace)
+            .map_or((text, ""), |(kind, argument)| (kind, argument.trim()));
+        match (kind, argument) {
+            ("none", "") => Some(GestureAction::None),
+            ("hide", "") => Some(GestureAction::Hide),
+            ("view", id) if !id.is_empty() => Some(GestureAction::View(id.to_string())),
+            ("command", command) if !command.is_empty() => Some(GestureAction::Command(command.to_string())),
+            ("keys", chord) if !chord.is_empty() => Some(GestureAction::Keys(chord.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for GestureAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GestureAction::None => write!(f, "none"),
+            GestureAction::View(id) => write!(f, "view {id}"),
+            GestureAction::Command(command) => write!(f, "command {command}"),
+            GestureAction::Keys(chord) => write!(f, "keys {chord}"),
+            GestureAction::Hide => write!(f, "hide"),
+        }
+    }
+}
+
+/// Actions for quick swipes and long presses, in the `[gestures]` section.
+/// Keyed by direction, eg. `top`, or by direction and the key the gesture starts on, eg. `left@Backspace`,
+/// which takes precedence over the direction alone, or by a recorded shape, eg. `shape:circle`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GestureActions {
+    pub actions: BTreeMap<String, GestureAction>,
+}
+
+impl Default for GestureActions {
+    fn default() -> Self {
+        let view = |id: &str| GestureAction::View(id.to_string());
+        GestureActions {
+            actions: BTreeMap::from([
+                (String::from("top_left"), view("compact_qwerty")),
+                (String::from("top"), view("settings")),
+                (String::from("top_right"), view("compact_qwerty")),
+                (String::from("right"), view("compact_qwerty")),
+                (String::from("bottom_right"), view("launcher")),
+                (String::from("bottom"), GestureAction::Hide),
+                (String::from("bottom_left"), view("compact_qwerty")),
+                (String::from("left"), view("compact_qwerty")),
+                (String::from("long_press"), view("pick")),
+            ]),
+        }
+    }
+}
+
+impl GestureActions {
+    /// Config key for a direction, and the key the gesture starts on.
+    pub fn key(direction: &str, region: Option<&str>) -> String {
+        match region {
+            Some(region) => format!("{direction}@{region}"),
+            None => direction.to_string(),
+        }
+    }
+
+    /// The action for a gesture, the one for the key it started on if there is one.
+    pub fn get(&self, direction: &str, region: Option<&str>) -> GestureAction {
+        region
+            .and_then(|region| self.actions.get(&Self::key(direction, Some(region))))
+            .or_else(|| self.actions.get(direction))
+            .cloned()
+            .unwrap_or(GestureAction::None)
+    }
+
+    /// Config key for a recorded shape, eg. `shape:circle`.
+    pub fn shape_key(name: &str) -> String {
+        format!("shape:{name}")
+    }
+
+    pub fn set(&mut self, key: String, action: GestureAction) {
+        self.actions.insert(key, action);
+    }
+}
+
+/// Numbers that decide how glides are scored, see `surfboard tune` to fit them to recorded glides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchParams {
+    pub sigma: f32, // pixels, how far from the key center the position weight falls off
+    pub edge_weight: u32, // added to the first and last key of a glide
+    pub min_weight: u32, // keys below this weight are not part of the word
+    pub min_distance: f32, // pixels between the points used for angles
+    pub language_model_weight: f32, // path weight per log10 of language model probability
+    pub dwell_weight: f32, // added per ms the finger stays over a key
+    pub max_dwell: u32, // ms, lingering longer than this adds no more weight
+    pub slow_speed: f32, // pixels per ms, slower than this over a key shows intent
+    pub slow_weight: u32, // added to keys the finger slowed down over
+    pub double_dwell: u32, // ms, pausing this long on a key glides a double letter
+    pub double_weight: u32, // added to words with a double letter where the finger paused or looped
+    pub double_turning: f32, // degrees turned over a key, a small loop is a double letter
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        SearchParams {
+            sigma: 15.0,
+            edge_weight: 200,
+            min_weight: 100,
+            min_distance: 15.0,
+            language_model_weight: 40.0,
+            dwell_weight: 0.2,
+            max_dwell: 300,
+            slow_speed: 0.3,
+            slow_weight: 40,
+            double_dwell: 150,
+            double_weight: 60,
+            double_turning: 300.0,
+        }
+    }
+}
+
+impl SearchParams {
+    /// Reads the params from an ini section, keeping the current value of anything missing or malformed.
+    pub fn read_section(&mut self, section: &BTreeMap<String, String>) {
+        fn read<T: std::str::FromStr>(section: &BTreeMap<String, String>, key: &str, value: &mut T) {
+            let Some(text) = section.get(key) else {
+                return
+            };
+            match text.parse() {
+                Ok(parsed) => *value = parsed,
+                Err(_) => warn!("Ignoring search param {} = {:?}, it is not a number", key, text),
+            }
+        }
+        read(section, "sigma", &mut self.sigma);
+        read(section, "edge_weight", &mut self.edge_weight);
+        read(section, "min_weight", &mut self.min_weight);
+        read(section, "min_distance", &mut self.min_distance);
+        read(section, "language_model_weight", &mut self.language_model_weight);
+        read(section, "dwell_weight", &mut self.dwell_weight);
+        read(section, "max_dwell", &mut self.max_dwell);
+        read(section, "slow_speed", &mut self.slow_speed);
+        read(section, "slow_weight", &mut self.slow_weight);
+        read(section, "double_dwell", &mut self.double_dwell);
+        read(section, "double_weight", &mut self.double_weight);
+        read(section, "double_turning", &mut self.double_turning);
+    }
+
+    pub fn to_section(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (String::from("sigma"), self.sigma.to_string()),
+            (String::from("edge_weight"), self.edge_weight.to_string()),
+            (String::from("min_weight"), self.min_weight.to_string()),
+            (String::from("min_distance"), self.min_distance.to_string()),
+            (String::from("language_model_weight"), self.language_model_weight.to_string()),
+            (String::from("dwell_weight"), self.dwell_weight.to_string()),
+            (String::from("max_dwell"), self.max_dwell.to_string()),
+            (String::from("slow_speed"), self.slow_speed.to_string()),
+            (String::from("slow_weight"), self.slow_weight.to_string()),
+            (String::from("double_dwell"), self.double_dwell.to_string()),
+            (String::from("double_weight"), self.double_weight.to_string()),
+            (String::from("double_turning"), self.double_turning.to_string()),
+        ])
+    }
+
+    /// Loads a profile from $XDG_CONFIG_HOME/surfboard/profiles/<name>.ini, over the defaults.
+    pub fn load_profile(name: &str) -> Option<Self> {
+        let path = xdg::BaseDirectories::with_prefix("surfboard")
+            .ok()?
+            .find_config_file(format!("{PROFILE_DIR}/{name}.ini"))?;
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => {
+                error!("Failed to load search profile \"{}\", with error: {}", path.display(), err);
+                return None
+            }
+        };
+        let mut params = SearchParams::default();
+        if let Some(section) = parse_ini(&text).get("search") {
+            params.read_section(section);
+        }
+        Some(params)
+    }
+
+    pub fn save_profile(&self, name: &str) -> io::Result<PathBuf> {
+        let path = xdg::BaseDirectories::with_prefix("surfboard")?.place_config_file(format!("{PROFILE_DIR}/{name}.ini"))?;
+        let ini = Ini::from([(String::from("search"), self.to_section())]);
+        std::fs::write(&path, format_ini(&ini))?;
+        Ok(path)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            languages: vec![String::from("en")],
+            layout_languages: BTreeMap::from([
+                (String::from("compact_qwerty"), String::from("en")),
+            ]),
+            search_profile: None,
+            search: SearchParams::default(),
+            typing: TypingRules::default(),
+            gestures: GestureActions::default(),
+            theme: String::from("dark"),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Self {
+        let Some(path) = xdg::BaseDirectories::with_prefix("surfboard")
+            .ok()
+            .and_then(|dirs| dirs.find_config_file(globals::CONFIG_FILE)) else {
+            return Config::default()
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Config::from_ini(&parse_ini(&text)),
+            Err(err) => {
+                error!("Failed to load config \"{}\", with error: {}", path.display(), err);
+                Config::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let path = match xdg::BaseDirectories::with_prefix("surfboard").map(|dirs| dirs.place_config_file(globals::CONFIG_FILE)) {
+            Ok(Ok(path)) => path,
+            _ => {
+                error!("Unable to find the xdg config dir, settings will not be saved");
+                return
+            }
+        };
+        if let Err(err) = std::fs::write(&path, format_ini(&self.to_ini())) {
+            error!("Failed to save config \"{}\", with error: {}", path.display(), err);
+        }
+    }
+
+    fn from_ini(ini: &Ini) -> Self {
+        let mut config = Config::default();
+
+        if let Some(languages) = ini.get("languages") {
+            if let Some(enabled) = languages.get("enabled") {
+                config.languages = split_list(enabled);
+            }
+        }
+
+        if let Some(layouts) = ini.get("layouts") {
+            config.layout_languages.extend(layouts.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+
+        // a profile replaces the search params in this file
+        if let Some(search) = ini.get("search") {
+            config.search.read_section(search);
+            config.search_profile = search.get("profile").cloned();
+        }
+        if let Some(name) = config.search_profile.as_ref() {
+            match SearchParams::load_profile(name) {
+                Some(params) => config.search = params,
+                None => warn!("Search profile \"{}\" not found, using the search params from the config", name),
+            }
+        }
+
+        if let Some(typing) = ini.get("typing") {
+            for rule in TypingRule::ALL {
+                match typing.get(rule.id()).map(|value| value.parse()) {
+                    Some(Ok(enabled)) => config.typing.set(rule, enabled),
+                    Some(Err(_)) => warn!("Ignoring typing rule {}, it is not true or false", rule.id()),
+                    None => {}
+                }
+            }
+        }
+
+        if let Some(gestures) = ini.get("gestures") {
+            for (key, value) in gestures {
+                match GestureAction::parse(value) {
+                    Some(action) => config.gestures.set(key.clone(), action),
+                    None => warn!("Ignoring gesture {} = {:?}, it is not an action", key, value),
+                }
+            }
+        }
+
+        if let Some(theme) = ini.get("appearance").and_then(|appearance| appearance.get("theme")) {
+            config.theme = theme.clone();
+        }
+
+        config
+    }
+
+    fn to_ini(&self) -> Ini {
+        let mut ini = Ini::new();
+        ini.entry(String::from("languages")).or_default()
+            .insert(String::from("enabled"), self.languages.join(","));
+        ini.insert(String::from("layouts"), self.layout_languages.clone());
+
+        // the profile is saved separately, so only write its name
+        let search = ini.entry(String::from("search")).or_default();
+        match self.search_profile.as_ref() {
+            Some(name) => { search.insert(String::from("profile"), name.clone()); }
+            None => search.extend(self.search.to_section()),
+        }
+
+        let typing = ini.entry(String::from("typing")).or_default();
+        for rule in TypingRule::ALL {
+            typing.insert(rule.id().to_string(), self.typing.get(rule).to_string());
+        }
+
+        ini.insert(String::from("gestures"), self.gestures.actions
+            .iter()
+            .map(|(key, action)| (key.clone(), action.to_string()))
+            .collect());
+        ini.entry(String::from("appearance")).or_default()
+            .insert(String::from("theme"), self.theme.clone());
+        ini
+    }
+}
+
+
+/// Returns the current config.
+/// Don't hold on to it while calling [`update`], as that will deadlock.
+pub fn get() -> RwLockReadGuard<'static, Config> {
+    CONFIG.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Changes the config and saves it.
+pub fn update(change: impl FnOnce(&mut Config)) {
+    let mut config = CONFIG.write().unwrap_or_else(|err| err.into_inner());
+    change(&mut config);
+    config.save();
+}
+
+
+/// Reads ini text with `pretty_ini`, into sections of key value pairs.
+/// Keys before the first section are put in the "" section, `#` and `;` comments are skipped.
+pub fn parse_ini(text: &str) -> Ini {
+    let mut file = ini_file::IniFile::default();
+    file.set_buffer(text.lines().map(|line| line.trim().to_string()).collect());
+    let mut parsed = ini::Ini::default();
+    if let Err(err) = parsed.load(&mut file) {
+        warn!("Failed to parse ini, with error: {}", err);
+    }
+
+    let mut result = Ini::new();
+    for name in parsed.get_all_table_names() {
+        let Ok(table) = parsed.get_table_ref(&name) else {
+            continue
+        };
+        let section = match name.as_str() {
+            ini::TABLE_NAME_ROOT => String::new(),
+            _ => name.clone(),
+        };
+        for variable in table.content.iter() {
+            // pretty_ini reads a comment with an `=` in it as a key
+            if variable.unknow_element.is_some() || variable.key.starts_with(['#', ';']) {
+                continue
+            }
+            result.entry(section.clone()).or_default().insert(variable.key.clone(), variable.value.clone());
+        }
+    }
+    result
+}
+
+/// Writes sections of key value pairs as ini text with `pretty_ini`.
+/// The text is written by the caller, as `IniFile::save` doesn't truncate a file that gets shorter.
+pub fn format_ini(ini: &Ini) -> String {
+    let mut formatted = ini::Ini::default();
+    for (section, values) in ini {
+        let name = match section.is_empty() {
+            true => ini::TABLE_NAME_ROOT.to_string(),
+            false => section.clone(),
+        };
+        let Ok(table) = formatted.add_table(&name) else {
+            continue
+        };
+        for (key, value) in values {
+            table.add_variable(Variable {
+                key: key.clone(),
+                value: value.clone(),
+                unknow_element: None,
+            });
+        }
+    }
+    formatted.make_ini_file_buffer().join("\n")
+}
+
+/// Splits a comma separated list, dropping empty items.
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trips_through_ini_text() {
+        let mut config = Config::default();
+        config.languages = vec![String::from("en"), String::from("de")];
+        config.layout_languages.insert(String::from("compact_qwertz"), String::from("de"));
+        config.search.sigma = 12.5;
+        config.search.max_dwell = 250;
+        config.typing.set(TypingRule::AutoCapitalize, false);
+        config.gestures.set(GestureActions::key("left", Some("Backspace")), GestureAction::Keys(String::from("ctrl+backspace")));
+        config.gestures.set(GestureActions::shape_key("circle"), GestureAction::Command(String::from("foot -e htop")));
+        config.theme = String::from("light");
+
+        let loaded = Config::from_ini(&parse_ini(&format_ini(&config.to_ini())));
+        assert_eq!(loaded.languages, config.languages);
+        assert_eq!(loaded.layout_languages, config.layout_languages);
+        assert_eq!(loaded.search_profile, None);
+        assert_eq!(loaded.search, config.search);
+        assert_eq!(loaded.typing, config.typing);
+        assert_eq!(loaded.gestures, config.gestures);
+        assert_eq!(loaded.theme, config.theme);
+    }
+
+    #[test]
+    fn comments_are_skipped_even_with_an_equals_sign() {
+        let ini = parse_ini("\
+            top = hide\n\
+            # bottom = view settings\n\
+            [appearance]\n\
+            ; theme = dark\n\
+            theme = light\n\
+        ");
+        assert_eq!(ini.get("").and_then(|root| root.get("top")).map(String::as_str), Some("hide"));
+        assert_eq!(ini.get("").map(|root| root.len()), Some(1));
+        assert_eq!(ini["appearance"].len(), 1);
+        assert_eq!(ini["appearance"]["theme"], "light");
+    }
+
+    #[test]
+    fn parses_gesture_actions() {
+        assert_eq!(GestureAction::parse("none"), Some(GestureAction::None));
+        assert_eq!(GestureAction::parse(" hide "), Some(GestureAction::Hide));
+        assert_eq!(GestureAction::parse("view settings"), Some(GestureAction::View(String::from("settings"))));
+        assert_eq!(GestureAction::parse("command foot -e htop"), Some(GestureAction::Command(String::from("foot -e htop"))));
+        assert_eq!(GestureAction::parse("keys ctrl+shift+t"), Some(GestureAction::Keys(String::from("ctrl+shift+t"))));
+
+        // every action reads back what it writes
+        for action in [GestureAction::None, GestureAction::Hide, GestureAction::View(String::from("pick")), GestureAction::Keys(String::from("ctrl+c"))] {
+            assert_eq!(GestureAction::parse(&action.to_string()), Some(action));
+        }
+
+        assert_eq!(GestureAction::parse("view"), None);
+        assert_eq!(GestureAction::parse("hide now"), None);
+        assert_eq!(GestureAction::parse("launch foot"), None);
+        assert_eq!(GestureAction::parse(""), None);
+    }
+}
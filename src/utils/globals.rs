@@ -4,5 +4,21 @@ pub static ICON_KEYBOARD: &[u8] = include_bytes!("../../res/keyboard-solid.svg")
 pub static ICON_ROCKET: &[u8] = include_bytes!("../../res/rocket-solid.svg");
 pub static ICON_TABLE: &[u8] = include_bytes!("../../res/table-cells-solid.svg");
 pub static ICON_TRUCK: &[u8] = include_bytes!("../../res/truck-fast-solid.svg");
+pub static ICON_GLOBE: &[u8] = include_bytes!("../../res/globe.svg");
+pub static ICON_BACKSPACE: &[u8] = include_bytes!("../../res/delete-left-solid.svg");
 
-pub static DICTIONARY: &str = include_str!("../../res/dictionary.txt");
\ No newline at end of file
+pub static DICTIONARY: &str = include_str!("../../res/dictionary.txt");
+pub static HANDWRITING_LATIN: &str = include_str!("../../res/handwriting/latin.txt");
+pub static CONTRACTIONS: &[(&str, &str)] = &[ // language tag, contractions and elisions
+    ("en", include_str!("../../res/contractions/en.txt")),
+    ("fr", include_str!("../../res/contractions/fr.txt")),
+];
+pub static THEMES: &[(&str, &str)] = &[ // built in themes, by id
+    ("dark", include_str!("../../res/themes/dark.ini")),
+    ("light", include_str!("../../res/themes/light.ini")),
+    ("high_contrast", include_str!("../../res/themes/high_contrast.ini")),
+];
+
+pub static NGRAM_FILE: &str = "ngram.bin"; // language model, searched for in the xdg data dirs
+pub static USER_DICTIONARY_FILE: &str = "user_dictionary.tsv"; // learned words, in the xdg data home
+pub static CONFIG_FILE: &str = "surfboard.ini"; // settings, in the xdg config home
\ No newline at end of file
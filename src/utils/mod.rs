@@ -1,3 +1,5 @@
 pub mod functions;
 pub mod globals;
-pub mod debug;
\ No newline at end of file
+pub mod debug;
+pub mod config;
+pub mod theme;
\ No newline at end of file
@@ -0,0 +1,203 @@
+use iced::{
+    Color,
+    Font
+};
+use std::collections::{
+    BTreeMap,
+    BTreeSet
+};
+use std::sync::{
+    LazyLock,
+    Mutex,
+    RwLock,
+    RwLockReadGuard
+};
+use super::*;
+use super::config::parse_ini;
+
+static THEME_DIR: &str = "themes"; // user themes, in the xdg config home
+static THEME: LazyLock<RwLock<Theme>> = LazyLock::new(|| RwLock::new(Theme::load(&config::get().theme)));
+static FONT_NAMES: LazyLock<Mutex<BTreeSet<&'static str>>> = LazyLock::new(|| Mutex::new(BTreeSet::new())); // see `font_name`
+
+/// Kinds of key, each with its own fill in a theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyClass {
+    #[default]
+    Letter, // keys that type, including punctuation and the space bar
+    Function, // eg. backspace, enter and the globe key
+    Modifier, // eg. shift
+    Suggestion, // words in the suggestion bar
+}
+
+/// Colours and shapes the keyboard is drawn with.
+/// Themes are ini files, see `res/themes/dark.ini` for the format. Light, dark and high contrast themes are
+/// built in, and more can be added to $XDG_CONFIG_HOME/surfboard/themes/<id>.ini, which can also replace a built in one.
+/// Anything missing from a theme file keeps the value from the dark theme.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String, // as shown in the settings
+    pub background: Color,
+    pub text: Color,
+    pub accent: Color, // latched and locked modifiers
+    pub letter: Color, // key fills, see `KeyClass`
+    pub function: Color,
+    pub modifier: Color,
+    pub suggestion: Color,
+    pub pressed: Color, // drawn over a key while it is tapped
+    pub preview: Color, // the magnified key above a tap, and the word above a glide
+    pub ribbon: Color, // the trail left by a glide
+    pub ribbon_width: f32, // pixels, where the ribbon starts, it narrows as it fades
+    pub ribbon_fade: u64, // ms the ribbon takes to fade
+    pub corner_radius: f32,
+    pub font: Font,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: String::from("Dark"),
+            background: Color::from_rgba(0.21, 0.23, 0.25, 0.95),
+            text: Color::WHITE,
+            accent: Color::from_rgb(0.35, 0.6, 1.0),
+            letter: Color::TRANSPARENT,
+            function: Color::TRANSPARENT,
+            modifier: Color::TRANSPARENT,
+            suggestion: Color::TRANSPARENT,
+            pressed: Color::from_rgba(1.0, 1.0, 1.0, 0.25),
+            preview: Color::from_rgb(0.3, 0.32, 0.35),
+            ribbon: Color::from_rgb(0.6, 0.8, 1.0),
+            ribbon_width: 25.0,
+            ribbon_fade: 800,
+            corner_radius: 10.0,
+            font: Font::DEFAULT,
+        }
+    }
+}
+
+impl Theme {
+    /// Loads the theme with the id, from the user's themes then the built in ones, the default if there is none.
+    pub fn load(id: &str) -> Self {
+        let user = xdg::BaseDirectories::with_prefix("surfboard")
+            .ok()
+            .and_then(|dirs| dirs.find_config_file(format!("{THEME_DIR}/{id}.ini")));
+        if let Some(path) = user {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => return Theme::parse(&text),
+                Err(err) => error!("Failed to load theme \"{}\", with error: {}", path.display(), err),
+            }
+        }
+
+        match globals::THEMES.iter().find(|(builtin, _)| *builtin == id) {
+            Some((_, text)) => Theme::parse(text),
+            None => {
+                warn!("Theme \"{}\" not found, using the default", id);
+                Theme::default()
+            }
+        }
+    }
+
+    /// Reads a theme file, keeping the default of anything missing or malformed.
+    pub fn parse(text: &str) -> Self {
+        fn read<T>(section: Option<&BTreeMap<String, String>>, key: &str, value: &mut T, parse: impl Fn(&str) -> Option<T>) {
+            let Some(text) = section.and_then(|section| section.get(key)) else {
+                return
+            };
+            match parse(text) {
+                Some(parsed) => *value = parsed,
+                None => warn!("Ignoring theme value {} = {:?}, it is malformed", key, text),
+            }
+        }
+        let number = |text: &str| text.parse().ok();
+
+        let ini = parse_ini(text);
+        let mut theme = Theme::default();
+        let section = ini.get("theme");
+        read(section, "name", &mut theme.name, |text| Some(text.to_string()));
+        read(section, "background", &mut theme.background, parse_color);
+        read(section, "text", &mut theme.text, parse_color);
+        read(section, "accent", &mut theme.accent, parse_color);
+        read(section, "corner_radius", &mut theme.corner_radius, number);
+        read(section, "font", &mut theme.font, |name| match name.is_empty() {
+            true => Some(Font::DEFAULT),
+            false => Some(Font::with_name(font_name(name))),
+        });
+
+        let section = ini.get("keys");
+        read(section, "letter", &mut theme.letter, parse_color);
+        read(section, "function", &mut theme.function, parse_color);
+        read(section, "modifier", &mut theme.modifier, parse_color);
+        read(section, "suggestion", &mut theme.suggestion, parse_color);
+        read(section, "pressed", &mut theme.pressed, parse_color);
+        read(section, "preview", &mut theme.preview, parse_color);
+
+        let section = ini.get("ribbon");
+        read(section, "color", &mut theme.ribbon, parse_color);
+        read(section, "width", &mut theme.ribbon_width, number);
+        read(section, "fade", &mut theme.ribbon_fade, |text| text.parse().ok());
+        theme
+    }
+
+    pub fn key_fill(&self, class: KeyClass) -> Color {
+        match class {
+            KeyClass::Letter => self.letter,
+            KeyClass::Function => self.function,
+            KeyClass::Modifier => self.modifier,
+            KeyClass::Suggestion => self.suggestion,
+        }
+    }
+}
+
+
+/// Returns the current theme.
+pub fn get() -> RwLockReadGuard<'static, Theme> {
+    THEME.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Changes the theme and saves the choice in the config.
+pub fn set(id: &str) {
+    let theme = Theme::load(id);
+    *THEME.write().unwrap_or_else(|err| err.into_inner()) = theme;
+    config::update(|config| config.theme = id.to_string());
+}
+
+/// Ids of the built in themes, then those in the user's themes dir.
+pub fn ids() -> Vec<String> {
+    let mut ids: Vec<String> = globals::THEMES.iter().map(|(id, _)| id.to_string()).collect();
+    if let Ok(dirs) = xdg::BaseDirectories::with_prefix("surfboard") {
+        for path in dirs.list_config_files(THEME_DIR) {
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue
+            };
+            if path.extension().is_some_and(|extension| extension == "ini") && !ids.iter().any(|known| known == id) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+/// The font family name, which iced keeps for as long as it runs.
+/// Each name is leaked once, so switching between themes doesn't leak it again.
+fn font_name(name: &str) -> &'static str {
+    let mut names = FONT_NAMES.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(known) = names.get(name).copied() {
+        return known
+    }
+    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(leaked);
+    leaked
+}
+
+/// Parses `#rrggbb` or `#rrggbbaa`.
+fn parse_color(text: &str) -> Option<Color> {
+    let hex = text.trim().strip_prefix('#')?;
+    if !hex.is_ascii() || !matches!(hex.len(), 6 | 8) {
+        return None
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    let alpha = match hex.len() {
+        8 => channel(6)?,
+        _ => 255,
+    };
+    Some(Color::from_rgba8(channel(0)?, channel(2)?, channel(4)?, alpha as f32 / 255.0))
+}
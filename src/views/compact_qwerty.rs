@@ -1,13 +1,29 @@
 use iced::{
     widget::{
-        column, 
-        row,
+        Column,
+        Row,
     }, 
     Length,
+    Renderer,
+    Theme,
 };
 use crate::app::*;
 use crate::comp::*;
 use crate::utils::*;
+use crate::utils::theme::KeyClass;
+
+pub static SHIFT: &str = "Shift";
+pub static LANGUAGE: &str = "Language";
+pub static SETTINGS: &str = "Settings";
+
+/// Labels of the keys, top row first. Special keys use the labels above, and `BACKSPACE`.
+/// The synthesized glides are laid out from this too, so the tests follow the real layout.
+pub static ROWS: [&[&str]; 4] = [
+    &["q", "w", "e", "r", "t", "y", "u", "i", "o", "p"],
+    &["a", "s", "d", "f", "g", "h", "j", "k", "l"],
+    &[SHIFT, "z", "x", "c", "v", "b", "n", "m", BACKSPACE, "Enter"],
+    &["@", ":)", "     ", ".", LANGUAGE, ">", "^", "<", SETTINGS],
+];
 
 
 #[derive(Copy, Debug, Clone)]
@@ -19,57 +35,13 @@ impl ViewTrait for CompactQwertyView {
         CompactQwertyView {}
     }
 
-    fn view(&self, _view_handler: &ViewHandler) -> iced::Element<main_app::Message> {
-        column![
-            row![
-                key_glide("q"),
-                key_glide("w"),
-                key_glide("e"),
-                key_glide("r"),
-                key_glide("t"),
-                key_glide("y"),
-                key_glide("u"),
-                key_glide("i"),
-                key_glide("o"),
-                key_glide("p"),
-            ].padding(0).width(Length::Fill).height(Length::Fill),
-
-            row![
-                key_glide("a"),
-                key_glide("s"),
-                key_glide("d"),
-                key_glide("f"),
-                key_glide("g"),
-                key_glide("h"),
-                key_glide("j"),
-                key_glide("k"),
-                key_glide("l"),
-            ].padding(0).width(Length::Fill).height(Length::Fill),
-
-            row![
-                key_glide("z"),
-                key_glide("x"),
-                key_glide("c"),
-                key_glide("v"),
-                key_glide("b"),
-                key_glide("n"),
-                key_glide("m"),
-                key_from_str("Enter"),
-            ].padding(0).width(Length::Fill).height(Length::Fill),
-
-            row![
-                key_from_str("@"),
-                key_from_str(":)"),
-                key_from_str("     "),
-                key_from_str("."),
-                key_from_str("."),
-                key_from_str(">"),
-                key_from_str("^"),
-                key_from_str("<"),
-                key_from_str("^").on_press(main_app::Message::ViewHandler(view::Message::ChangeView(View::Settings))),
-            ].padding(0).width(Length::Fill).height(Length::Fill),
-
-        ].padding(0).width(Length::Fill).height(Length::Fill)
+    fn view(&self, view_handler: &ViewHandler) -> iced::Element<main_app::Message> {
+        Column::with_children(ROWS.iter().map(|labels| {
+            Row::with_children(labels.iter().map(|label| key(label, view_handler).into()))
+                .padding(0).width(Length::Fill).height(Length::Fill)
+                .into()
+        }))
+        .padding(0).width(Length::Fill).height(Length::Fill)
         .into()
     }
 
@@ -86,3 +58,25 @@ impl ViewTrait for CompactQwertyView {
     }
 }
 
+
+/// The key for a label in `ROWS`.
+fn key<'a>(label: &'static str, view_handler: &ViewHandler) -> Key<'a, main_app::Message, Theme, Renderer> {
+    match label {
+        _ if label == SHIFT => key_shift(view_handler.shift),
+        _ if label == LANGUAGE => key_language(view_handler.language.clone()),
+        _ if label == BACKSPACE => key_backspace(),
+        _ if label == SETTINGS => key_from_str("^").on_press(main_app::Message::ViewHandler(view::Message::ChangeView(View::Settings))).class(KeyClass::Function),
+        "Enter" | ">" | "^" | "<" => key_from_str(label).class(KeyClass::Function),
+        _ if label.chars().all(|c| c.is_alphabetic()) => key_glide(label),
+        _ => key_from_str(label),
+    }
+}
+
+/// The label a key in `ROWS` reports its bounds to the search with, None for keys that don't.
+pub fn search_label(label: &str) -> Option<&str> {
+    match label {
+        _ if label == SHIFT || label == LANGUAGE => None,
+        _ if label == SETTINGS => Some("^"),
+        _ => Some(label),
+    }
+}
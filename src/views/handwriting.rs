@@ -0,0 +1,47 @@
+use iced::{
+    widget::{
+        column,
+        row,
+    },
+    Length,
+};
+use crate::app::*;
+use crate::comp::*;
+use crate::utils::*;
+use crate::utils::theme::KeyClass;
+
+
+/// Writing area for handwriting, with a row of keys for spacing and punctuation.
+/// Strokes are drawn by the gesture ribbon and read by the gesture handler.
+#[derive(Copy, Debug, Clone)]
+pub struct HandwritingView {}
+
+
+impl ViewTrait for HandwritingView {
+    fn new() -> Self {
+        HandwritingView {}
+    }
+
+    fn view(&self, _view_handler: &ViewHandler) -> iced::Element<main_app::Message> {
+        column![
+            column![].width(Length::Fill).height(Length::FillPortion(3)), // writing area
+            row![
+                key_from_str("abc").on_press(main_app::Message::ViewHandler(view::Message::ChangeView(View::CompactQwerty))).class(KeyClass::Function),
+                key_from_str(","),
+                key_from_str("     "),
+                key_from_str("."),
+                key_backspace(),
+                key_from_str("Enter").class(KeyClass::Function),
+            ].padding(0).width(Length::Fill).height(Length::Fill),
+        ].padding(0).width(Length::Fill).height(Length::Fill)
+        .into()
+    }
+
+    fn has_gesture(&self) -> bool {
+        true
+    }
+
+    fn class(&self) -> View {
+        View::Handwriting
+    }
+}
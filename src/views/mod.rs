@@ -3,10 +3,12 @@ pub mod compact_qwerty;
 pub mod settings;
 pub mod quick_pick;
 pub mod pick;
+pub mod handwriting;
 
 // re-export
 pub use super::views::launcher::*;
 pub use super::views::settings::*;
 pub use super::views::compact_qwerty::*;
 pub use super::views::quick_pick::*;
-pub use super::views::pick::*;
\ No newline at end of file
+pub use super::views::pick::*;
+pub use super::views::handwriting::*;
\ No newline at end of file
@@ -11,7 +11,7 @@ use crate::utils::*;
 
 #[derive(Debug, Clone)]
 pub struct MiniPickView {
-    filter_views: [View; 3],
+    filter_views: [View; 4],
     shortcuts: Option<Vec<Shortcut>>,
 }
 
@@ -23,6 +23,7 @@ impl ViewTrait for MiniPickView {
             View::CompactQwerty,
             View::Settings,
             View::Launcher,
+            View::Handwriting,
         ];
 
 
@@ -1,9 +1,14 @@
 use iced::{
     widget::{
+        column,
         pick_list, 
         row, 
+        scrollable,
+        text_input,
         Button, 
+        Column,
         PickList, 
+        Row,
         Text}, 
         Element, 
         Length, 
@@ -12,6 +17,11 @@ use iced::{
     };
 use crate::app::*;
 use crate::utils::*;
+use crate::utils::config::{
+    GestureAction,
+    GestureActions,
+    TypingRule
+};
 
 
 #[derive(Copy, Debug, Clone)]
@@ -23,7 +33,7 @@ impl ViewTrait for SettingsView {
         SettingsView{}
     }
 
-    fn view(&self, _view_handler: &ViewHandler) -> Element<main_app::Message> {
+    fn view(&self, view_handler: &ViewHandler) -> Element<main_app::Message> {
         let view_main = Button::new(Text::new("main")).on_press(main_app::Message::ViewHandler(view::Message::ChangeView(View::CompactQwerty)));
         let view_launcher = Button::new(Text::new("launcher")).on_press(main_app::Message::ViewHandler(view::Message::ChangeView(View::Launcher)));
         
@@ -41,12 +51,144 @@ impl ViewTrait for SettingsView {
         )
         .placeholder("Edge");
 
-        row![
-            pick_dock,
-            pick_view,
-            view_main,
-            view_launcher,
+        let pick_theme = row![
+            Text::new(format!("Theme: {}", theme::get().name)).width(Length::Fill),
+            pick_list(
+                theme::ids(),
+                Some(config::get().theme.clone()),
+                |id| main_app::Message::ViewHandler(view::Message::Theme(id)),
+            ),
+        ].spacing(10);
+
+        // languages, enabled ones are cycled by the globe key
+        let enabled = config::get().languages.clone();
+        let languages = view_handler.languages
+            .iter()
+            .map(|tag| {
+                let label = match enabled.contains(tag) {
+                    true => format!("[x] {tag}"),
+                    false => format!("[ ] {tag}"),
+                };
+                Button::new(Text::new(label))
+                    .on_press(main_app::Message::SearchHandler(search::Message::ToggleLanguage(tag.clone())))
+                    .into()
+            });
+
+        // default language for each keyboard layout
+        let layout_languages = view_handler.views
+            .iter()
+            .filter(|view| view.has_gesture())
+            .map(|view| {
+                let class = view.class();
+                let selected = config::get().layout_languages.get(class.id()).cloned();
+                row![
+                    Text::new(view.name()).width(Length::Fill),
+                    pick_list(
+                        view_handler.languages.clone(),
+                        selected,
+                        move |tag| main_app::Message::ViewHandler(view::Message::LayoutLanguage(class, tag)),
+                    )
+                    .placeholder("Language"),
+                ].spacing(10).into()
+            });
+
+        // spacing, punctuation and capitals around typed words
+        let typing = config::get().typing.clone();
+        let typing_rules = TypingRule::ALL
+            .iter()
+            .map(|rule| {
+                let label = match typing.get(*rule) {
+                    true => format!("[x] {rule}"),
+                    false => format!("[ ] {rule}"),
+                };
+                Button::new(Text::new(label))
+                    .on_press(main_app::Message::OutputHandler(output::Message::ToggleRule(*rule)))
+                    .into()
+            });
+
+        // actions for quick swipes, those for a starting key can only be added in the config file
+        let gestures = config::get().gestures.clone();
+        let mut gesture_keys: Vec<(String, String)> = ActionDirection::ALL
+            .iter()
+            .map(|direction| (direction.id().to_string(), direction.to_string()))
+            .collect();
+        gesture_keys.extend(gestures.actions
+            .keys()
+            .filter(|key| key.contains('@'))
+            .map(|key| (key.clone(), key.clone())));
+        let gesture_actions = gesture_keys
+            .into_iter()
+            .map(|(key, label)| {
+                let (direction, region) = match key.split_once('@') {
+                    Some((direction, region)) => (direction, Some(region)),
+                    None => (key.as_str(), None),
+                };
+                row![
+                    Text::new(label).width(Length::Fill),
+                    pick_gesture_action(GestureActions::key(direction, region), gestures.get(direction, region)),
+                ].spacing(10).into()
+            });
+
+        // drawn shapes, started above the keys
+        let shapes = view_handler.shapes
+            .iter()
+            .map(|name| {
+                let key = GestureActions::shape_key(name);
+                let selected = gestures.get(&key, None);
+                row![
+                    Text::new(format!("shape {name}")).width(Length::Fill),
+                    pick_gesture_action(key, selected),
+                    Button::new(Text::new("forget")).on_press(main_app::Message::GestureHandler(gesture::Message::ForgetShape(name.clone()))),
+                ].spacing(10).into()
+            });
+        let record_shape: Element<main_app::Message> = match view_handler.recording_shape {
+            true => Text::new("Draw the shape to record it").into(),
+            false => row![
+                text_input("shape name", &view_handler.shape_name)
+                    .on_input(|name| main_app::Message::ViewHandler(view::Message::ShapeName(name)))
+                    .on_submit(main_app::Message::ViewHandler(view::Message::RecordShape)),
+                Button::new(Text::new("record shape")).on_press(main_app::Message::ViewHandler(view::Message::RecordShape)),
+            ].spacing(10).into(),
+        };
+
+        // record glides for `surfboard bench`, the next word picked or typed after each glide labels it
+        let record = Button::new(Text::new(match view_handler.recording {
+            true => "[x] record glides",
+            false => "[ ] record glides",
+        }))
+            .on_press(main_app::Message::SearchHandler(search::Message::ToggleRecording));
+
+        // learned words, which can be removed if they were learned by mistake
+        let learned_words = view_handler.learned_words
+            .iter()
+            .map(|item| {
+                row![
+                    Text::new(format!("{} ({})", item.word, item.count)).width(Length::Fill),
+                    Button::new(Text::new("forget")).on_press(main_app::Message::SearchHandler(search::Message::Forget(item.word.clone()))),
+                ].into()
+            });
+
+        column![
+            row![
+                pick_dock,
+                pick_view,
+                view_main,
+                view_launcher,
+            ],
+            pick_theme,
+            Text::new(format!("Languages (active: {})", view_handler.language)),
+            Row::with_children(languages).spacing(5),
+            Column::with_children(layout_languages).spacing(5),
+            Row::with_children(typing_rules).spacing(5),
+            Text::new("Gestures"),
+            Column::with_children(gesture_actions).spacing(5),
+            Column::with_children(shapes).spacing(5),
+            record_shape,
+            record,
+            Text::new(format!("Learned words: {}", view_handler.learned_words.len())),
+            scrollable(Column::with_children(learned_words).spacing(5)).height(Length::Fill),
         ]
+            .spacing(10)
             .padding(20)
             .width(Length::Fill)
             .into()
@@ -59,4 +201,19 @@ impl ViewTrait for SettingsView {
     fn icon(&self) -> &'static [u8] {
         globals::ICON_SETTINGS
     }
-}
\ No newline at end of file
+}
+
+
+/// Picks the action for a gesture, commands and key chords are set in the config file.
+fn pick_gesture_action<'a>(key: String, selected: GestureAction) -> Element<'a, main_app::Message> {
+    let mut actions = vec![GestureAction::None, GestureAction::Hide];
+    actions.extend(View::ALL.iter().map(|view| GestureAction::View(view.id().to_string())));
+    if !actions.contains(&selected) {
+        actions.push(selected.clone());
+    }
+    pick_list(
+        actions,
+        Some(selected),
+        move |action| main_app::Message::ViewHandler(view::Message::SetGestureAction(key.clone(), action)),
+    ).into()
+}